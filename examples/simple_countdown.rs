@@ -5,55 +5,81 @@
 //!
 //! Note: This example is for documentation purposes and cannot be run
 //! in a standard Rust environment as it requires WebAssembly.
+//!
+//! A timer runs only as long as something owns it: pending ticks hold a
+//! weak reference, so dropping the last `Rc` (or the `RunGuard` from
+//! `start_guarded`) stops it. Each example hands its timer back to the
+//! caller, which must keep it, e.g. in a component's state.
 
-use emom::countdown_timer::{CountdownTimer, TimerConfig};
+use emom::countdown_timer::{
+    CatchUpPolicy, CountdownTimer, RunGuard, TickEvent, TimerConfig, tick_count,
+};
+use std::rc::Rc;
 
 #[allow(dead_code)]
-fn example_basic_timer() {
+fn example_basic_timer() -> Rc<CountdownTimer<impl Fn(TickEvent) + 'static>> {
     // Create a timer that ticks every 100ms
     let config = TimerConfig::default();
 
-    let timer = CountdownTimer::new(config, |ticks| {
-        println!("Elapsed: {} tenths of a second", ticks);
-    });
+    let timer = CountdownTimer::new(
+        config,
+        tick_count(|ticks| {
+            println!("Elapsed: {} tenths of a second", ticks);
+        }),
+    );
 
     timer.start();
-    // Timer will now tick every 100ms, calling the callback
-    // In a real WASM app, this would integrate with your UI framework
+    // The timer ticks every 100ms for as long as the caller keeps this Rc;
+    // in a real WASM app it would live in your UI framework's state
+    timer
 }
 
 #[allow(dead_code)]
-fn example_countdown_from_duration() {
-    use std::cell::RefCell;
-    use std::rc::Rc;
+fn example_countdown_from_duration() -> Rc<CountdownTimer<impl Fn(TickEvent) + 'static>> {
+    let timer = CountdownTimer::new(
+        TimerConfig::default(),
+        tick_count(|ticks| {
+            let remaining = 600 - ticks; // 60 seconds in tenths
+            println!("Remaining: {}.{} seconds", remaining / 10, remaining % 10);
+        }),
+    );
 
-    let remaining = Rc::new(RefCell::new(600)); // 60 seconds in tenths
-
-    let remaining_clone = Rc::clone(&remaining);
-    let timer = CountdownTimer::new(TimerConfig::default(), move |_ticks| {
-        let mut rem = remaining_clone.borrow_mut();
-        if *rem > 0 {
-            *rem -= 1;
-            println!("Remaining: {}.{} seconds", *rem / 10, *rem % 10);
+    // Stop after 600 ticks and report completion once. The callback holds a
+    // Weak so it does not keep the timer alive itself.
+    let timer_clone = Rc::downgrade(&timer);
+    timer.set_target(600, move || {
+        if let Some(timer) = timer_clone.upgrade() {
+            println!("Done after {} ticks", timer.elapsed_ticks());
         }
     });
 
     timer.start();
+    // Dropping the returned Rc before the target stops the countdown early
+    timer
 }
 
 #[allow(dead_code)]
-fn example_custom_config() {
+fn example_custom_config() -> RunGuard<impl Fn(TickEvent) + 'static> {
     let config = TimerConfig {
-        interval_ms: 100,        // Tick every 100ms
-        sync_interval_ticks: 10, // Sync with wall clock every 10 ticks (1 second)
-        sync_threshold_ticks: 1, // Correct if drift exceeds 1 tick (100ms)
+        interval_ms: 100,               // Tick every 100ms
+        sync_interval_ticks: 10,        // Sync with wall clock every 10 ticks (1 second)
+        sync_threshold_ticks: 1,        // Correct if drift exceeds 1 tick (100ms)
+        catch_up: CatchUpPolicy::Burst, // Replay each missed tick after falling behind
+        align_to_wall_clock: false,     // Tick relative to start() rather than epoch time
+        speed: 1.0,                     // Real time; 10.0 runs ten times faster
     };
 
-    let timer = CountdownTimer::new(config, |ticks| {
-        println!("Tick {}", ticks);
+    let timer = CountdownTimer::new(config, |event| {
+        println!(
+            "Tick {} fired {:.1}ms late{}",
+            event.tick,
+            event.actual_ms - event.scheduled_ms,
+            if event.corrected { " (corrected)" } else { "" }
+        );
     });
 
-    timer.start();
+    // The guard stops the timer when dropped, even if clones of the Rc remain
+    timer.start_guarded()
 }
 
 fn main() {
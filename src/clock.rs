@@ -0,0 +1,189 @@
+//! Time sources for the countdown timer
+//!
+//! All clocks report milliseconds as `f64`, matching `Date.now()`. Only the
+//! difference between two readings matters, so the origin is arbitrary.
+
+use js_sys::Date;
+use std::cell::Cell;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+pub trait Clock {
+    /// Current time in milliseconds
+    fn now(&self) -> f64;
+
+    /// Current wall-clock time in milliseconds since the Unix epoch, used to
+    /// phase ticks across devices. Defaults to `now` for clocks that already
+    /// read epoch time.
+    fn epoch_ms(&self) -> f64 {
+        self.now()
+    }
+}
+
+fn system_epoch_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
+
+/// Browser monotonic clock backed by `performance.now()`
+///
+/// Unaffected by NTP corrections, DST or the user changing the device clock.
+/// Available on both the main thread and in workers.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerformanceClock;
+
+impl Clock for PerformanceClock {
+    fn now(&self) -> f64 {
+        performance_now()
+    }
+
+    fn epoch_ms(&self) -> f64 {
+        Date::now()
+    }
+}
+
+/// Browser wall clock backed by `Date.now()`
+///
+/// Jumps whenever the system time changes. The timer recovers from backward
+/// jumps, but forward jumps look like the tab being suspended.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now(&self) -> f64 {
+        Date::now()
+    }
+}
+
+/// Native clock backed by `std::time::Instant`, measured from construction
+#[derive(Debug, Clone, Copy)]
+pub struct InstantClock {
+    origin: Instant,
+}
+
+impl InstantClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for InstantClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for InstantClock {
+    fn now(&self) -> f64 {
+        self.origin.elapsed().as_secs_f64() * 1000.0
+    }
+
+    fn epoch_ms(&self) -> f64 {
+        system_epoch_ms()
+    }
+}
+
+/// Native clock backed by `tokio::time::Instant`, so paused test time applies
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy)]
+pub struct TokioClock {
+    origin: tokio::time::Instant,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioClock {
+    pub fn new() -> Self {
+        Self {
+            origin: tokio::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Default for TokioClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Clock for TokioClock {
+    fn now(&self) -> f64 {
+        self.origin.elapsed().as_secs_f64() * 1000.0
+    }
+
+    fn epoch_ms(&self) -> f64 {
+        system_epoch_ms()
+    }
+}
+
+/// Clock that only moves when told to, for deterministic tests
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: Cell<f64>,
+}
+
+impl ManualClock {
+    pub fn new(start_ms: f64) -> Self {
+        Self {
+            now: Cell::new(start_ms),
+        }
+    }
+
+    /// Move the clock forward (or backward, if negative) by `ms`
+    pub fn advance(&self, ms: f64) {
+        self.now.set(self.now.get() + ms);
+    }
+
+    pub fn set(&self, ms: f64) {
+        self.now.set(ms);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_advance() {
+        let clock = ManualClock::new(1000.0);
+        assert_eq!(clock.now(), 1000.0);
+        clock.advance(250.0);
+        assert_eq!(clock.now(), 1250.0);
+        clock.advance(-50.0);
+        assert_eq!(clock.now(), 1200.0);
+        clock.set(0.0);
+        assert_eq!(clock.now(), 0.0);
+    }
+
+    #[test]
+    fn test_epoch_ms() {
+        // 2020-09-13, well before any machine running these tests
+        assert!(InstantClock::new().epoch_ms() > 1_600_000_000_000.0);
+        assert_eq!(ManualClock::new(1234.0).epoch_ms(), 1234.0);
+    }
+
+    #[test]
+    fn test_instant_clock_is_monotonic() {
+        let clock = InstantClock::new();
+        let a = clock.now();
+        let b = clock.now();
+        assert!(a >= 0.0);
+        assert!(b >= a);
+    }
+}
@@ -1,170 +1,3277 @@
-//! Simple countdown timer - straightforward implementation
+//! Drift-correcting tick timer with pluggable time and scheduling backends
+//!
+//! A [`CountdownTimer`] counts fixed-length ticks on a timeline anchored at
+//! its start time, not by chaining timeouts, so late timeouts do not add up.
+//! Every `sync_interval_ticks` the count is checked against the clock and,
+//! if it drifted more than `sync_threshold_ticks`, jumped to where the clock
+//! says it should be. Targets and deadlines are corrected exactly, so a
+//! countdown never completes early.
+//!
+//! # Ticks
+//!
+//! Each callback gets a [`TickEvent`] whose `tick` only ever increases while
+//! the timer runs, until `set_interval` renumbers ticks. How a jump is
+//! delivered follows [`CatchUpPolicy`]: `Skip` sends the corrected tick
+//! alone, `Burst` replays every tick in between and `Coalesce` sends one
+//! event whose `missed_ticks` counts the ticks folded in besides `tick`
+//! itself, so the count moved by `missed_ticks + 1`. A
+//! [`CountdownTimer::resync`] reports ticks the same way.
+//!
+//! # Ownership
+//!
+//! Timers live in an `Rc`, and pending timeouts, visibility listeners and
+//! animation frames hold only weak references. A timer runs as long as its
+//! owner keeps the `Rc` or the [`RunGuard`] from
+//! [`CountdownTimer::start_guarded`]; dropping it stops the timer and cancels
+//! the pending timeout. Callbacks may call any method on the timer, see
+//! [`CountdownTimer`] for how that interrupts the tick being delivered.
+//!
+//! # Backends
+//!
+//! Time comes from a [`Clock`] and timeouts from a [`Scheduler`]. `new` uses
+//! `performance.now()` and `setTimeout`, `new_in_worker` runs the timeouts in
+//! a Web Worker, and `with_backend` accepts any pair, e.g. tokio timers or
+//! the virtual time of [`crate::simulation`].
 
-use gloo_timers::callback::Timeout;
-use js_sys::Date;
+use crate::clock::{Clock, PerformanceClock};
+use crate::scheduler::{BrowserScheduler, Scheduler, TaskHandle, WorkerScheduler};
+use futures::Stream;
+use futures::channel::mpsc::{UnboundedReceiver, unbounded};
+use gloo_events::EventListener;
 use std::cell::{Cell, RefCell};
-use std::rc::Rc;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use wasm_bindgen::JsValue;
+use web_sys::VisibilityState;
 
-#[derive(Debug, Clone, Copy)]
+/// How ticks missed while the timer fell behind are delivered once drift is corrected
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CatchUpPolicy {
+    /// Jump to the corrected tick count with a single callback
+    #[default]
+    Skip,
+    /// Invoke the callback once for every missed tick
+    Burst,
+    /// Jump with a single callback and report the jump via `missed_ticks`
+    Coalesce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimerConfig {
     pub interval_ms: u32,
     pub sync_interval_ticks: usize,
     pub sync_threshold_ticks: usize,
+    pub catch_up: CatchUpPolicy,
+    /// Phase ticks to whole multiples of `interval_ms` in wall-clock time, so
+    /// timers on different devices tick at the same moment
+    pub align_to_wall_clock: bool,
+    /// Real time multiplier; at 10.0 each `interval_ms` tick takes a tenth as
+    /// long, so a 20 minute workout previews in 2 minutes
+    pub speed: f64,
 }
 
 impl Default for TimerConfig {
     fn default() -> Self {
+        Self::tenths()
+    }
+}
+
+/// Longest delay `setTimeout` accepts before it fires immediately
+const MAX_INTERVAL_MS: u32 = i32::MAX as u32;
+
+impl TimerConfig {
+    /// 100ms ticks, checked against the clock every second
+    pub fn tenths() -> Self {
         Self {
             interval_ms: 100,
             sync_interval_ticks: 10,
             sync_threshold_ticks: 1,
+            catch_up: CatchUpPolicy::Skip,
+            align_to_wall_clock: false,
+            speed: 1.0,
+        }
+    }
+
+    /// 1000ms ticks, checked against the clock every tick
+    pub fn seconds() -> Self {
+        Self {
+            interval_ms: 1000,
+            sync_interval_ticks: 1,
+            sync_threshold_ticks: 1,
+            catch_up: CatchUpPolicy::Skip,
+            align_to_wall_clock: false,
+            speed: 1.0,
+        }
+    }
+
+    pub fn builder() -> TimerConfigBuilder {
+        TimerConfigBuilder {
+            config: Self::default(),
+        }
+    }
+
+    /// Length of each tick before `speed` scaling
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.into())
+    }
+
+    /// Time covered by `ticks` ticks, before `speed` scaling
+    pub fn ticks_to_duration(&self, ticks: usize) -> Duration {
+        Duration::from_millis((ticks as u64).saturating_mul(self.interval_ms.into()))
+    }
+
+    /// Ticks needed to cover `duration`, rounded up so a target set from it
+    /// never completes early
+    pub fn duration_to_ticks(&self, duration: Duration) -> usize {
+        duration
+            .as_millis()
+            .div_ceil(self.interval_ms.max(1).into())
+            .try_into()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Real milliseconds per tick after `speed` scaling
+    pub fn tick_ms(&self) -> f64 {
+        self.interval_ms as f64 / self.speed
+    }
+
+    pub fn validate(&self) -> Result<(), TimerConfigError> {
+        if self.interval_ms == 0 {
+            return Err(TimerConfigError::ZeroInterval);
+        }
+        if self.interval_ms > MAX_INTERVAL_MS {
+            return Err(TimerConfigError::IntervalTooLong(self.interval_ms));
+        }
+        if !(self.speed.is_finite() && self.speed > 0.0 && self.tick_ms() >= 1.0) {
+            return Err(TimerConfigError::InvalidSpeed(self.speed));
+        }
+        if self.sync_interval_ticks == 0 {
+            return Err(TimerConfigError::ZeroSyncInterval);
+        }
+        if self.sync_threshold_ticks == 0 {
+            return Err(TimerConfigError::ZeroSyncThreshold);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerConfigError {
+    /// `interval_ms` of 0 would reschedule in a busy loop
+    ZeroInterval,
+    /// `interval_ms` beyond what `setTimeout` supports
+    IntervalTooLong(u32),
+    /// `speed` that is not positive or scales ticks below 1ms
+    InvalidSpeed(f64),
+    /// `sync_interval_ticks` of 0 would never check for drift
+    ZeroSyncInterval,
+    /// `sync_threshold_ticks` of 0 would correct rounding noise on every sync
+    ZeroSyncThreshold,
+    /// A [`TimerSnapshot`] time that is NaN or infinite would leave no next
+    /// tick to schedule
+    NonFiniteSnapshotTime,
+}
+
+impl std::fmt::Display for TimerConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZeroInterval => write!(f, "interval_ms must be greater than 0"),
+            Self::IntervalTooLong(ms) => {
+                write!(f, "interval_ms {} exceeds {}", ms, MAX_INTERVAL_MS)
+            }
+            Self::InvalidSpeed(speed) => write!(
+                f,
+                "speed {} must be positive and leave ticks of at least 1ms",
+                speed
+            ),
+            Self::ZeroSyncInterval => write!(f, "sync_interval_ticks must be greater than 0"),
+            Self::ZeroSyncThreshold => write!(f, "sync_threshold_ticks must be greater than 0"),
+            Self::NonFiniteSnapshotTime => write!(f, "snapshot times must be finite"),
+        }
+    }
+}
+
+impl std::error::Error for TimerConfigError {}
+
+/// Builds a [`TimerConfig`] starting from the defaults, validating on `build`
+#[derive(Debug, Clone, Copy)]
+pub struct TimerConfigBuilder {
+    config: TimerConfig,
+}
+
+impl TimerConfigBuilder {
+    pub fn interval_ms(mut self, interval_ms: u32) -> Self {
+        self.config.interval_ms = interval_ms;
+        self
+    }
+
+    /// Tick length as a `Duration`, in whole milliseconds; anything finer is dropped
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.config.interval_ms = interval.as_millis().try_into().unwrap_or(u32::MAX);
+        self
+    }
+
+    pub fn sync_interval_ticks(mut self, ticks: usize) -> Self {
+        self.config.sync_interval_ticks = ticks;
+        self
+    }
+
+    pub fn sync_threshold_ticks(mut self, ticks: usize) -> Self {
+        self.config.sync_threshold_ticks = ticks;
+        self
+    }
+
+    pub fn catch_up(mut self, catch_up: CatchUpPolicy) -> Self {
+        self.config.catch_up = catch_up;
+        self
+    }
+
+    pub fn align_to_wall_clock(mut self, align: bool) -> Self {
+        self.config.align_to_wall_clock = align;
+        self
+    }
+
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.config.speed = speed;
+        self
+    }
+
+    pub fn build(self) -> Result<TimerConfig, TimerConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// What [`CountdownTimer::resync`] found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resync {
+    /// Time since the last tick, start or resume, in milliseconds
+    pub gap_ms: f64,
+    /// Ticks the count jumped forward to catch up with the clock
+    pub missed_ticks: usize,
+}
+
+/// Keeps a [`CountdownTimer::resync_when_visible`] listener registered until dropped
+#[must_use = "the listener is removed when this is dropped"]
+pub struct VisibilityListener {
+    _listener: EventListener,
+}
+
+/// Keeps a [`CountdownTimer::animate`] loop running until dropped
+#[must_use = "the animation stops when this is dropped"]
+pub struct AnimationLoop {
+    _frames: Rc<FrameLoop>,
+}
+
+/// Shared by an [`AnimationLoop`] and its pending frame, which only holds a
+/// weak reference so dropping the loop cancels it
+struct FrameLoop {
+    pending: Cell<Option<TaskHandle>>,
+    on_frame: Box<dyn Fn(DisplayFrame)>,
+}
+
+/// Smoothly interpolated position of the timer, for display only
+///
+/// Between ticks the elapsed time follows the clock from the drift-corrected
+/// timeline, but never runs past the next tick still to be delivered, so the
+/// display cannot get ahead of the tick callbacks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayFrame {
+    /// Ticks delivered so far
+    pub tick: usize,
+    /// Elapsed time in milliseconds before `speed` scaling, like
+    /// `tick * interval_ms` but continuous
+    pub elapsed_ms: f64,
+    /// Fraction of the target reached, from 0.0 to 1.0; None without a target
+    pub progress: Option<f64>,
+}
+
+/// Everything known about a single tick, passed to tick callbacks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickEvent {
+    /// Ticks elapsed since start, including this one
+    pub tick: usize,
+    /// Length of each tick when this one was counted, in milliseconds before
+    /// `speed` scaling
+    pub interval_ms: u32,
+    /// Clock time the timeout was scheduled to fire, in milliseconds
+    pub scheduled_ms: f64,
+    /// Clock time the timeout actually fired, in milliseconds
+    pub actual_ms: f64,
+    /// How far the tick count trailed the clock before any correction, in
+    /// milliseconds; negative when ticks ran ahead
+    pub drift_ms: f64,
+    /// This tick resynced the tick count with the clock
+    pub corrected: bool,
+    /// Ticks folded into this callback by a [`CatchUpPolicy::Coalesce`]
+    /// correction, besides `tick` itself, so the count advanced by
+    /// `missed_ticks + 1`; always 0 for other policies
+    pub missed_ticks: usize,
+    /// Wall-clock time left until the deadline set by
+    /// [`CountdownTimer::set_deadline`], in milliseconds; None without one.
+    /// Only a tick due at or after the deadline reports 0; one passing
+    /// between ticks is reported through `on_complete` alone
+    pub remaining_ms: Option<f64>,
+}
+
+impl TickEvent {
+    /// Time counted up to and including this tick, before `speed` scaling
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis((self.tick as u64).saturating_mul(self.interval_ms.into()))
+    }
+}
+
+/// Adapt a callback that only wants the tick count
+///
+/// ```no_run
+/// use emom::countdown_timer::{CountdownTimer, TimerConfig, tick_count};
+///
+/// let timer = CountdownTimer::new(
+///     TimerConfig::default(),
+///     tick_count(|ticks| println!("Tick {}", ticks)),
+/// );
+/// ```
+pub fn tick_count(on_tick: impl Fn(usize) + 'static) -> impl Fn(TickEvent) + 'static {
+    move |event| on_tick(event.tick)
+}
+
+type Subscribers = RefCell<Vec<(u64, Rc<dyn Fn(TickEvent)>)>>;
+
+/// Keeps a [`CountdownTimer::subscribe`] listener attached; dropping it unsubscribes
+#[must_use = "the listener is removed when the subscription is dropped"]
+pub struct Subscription {
+    id: u64,
+    subscribers: Weak<Subscribers>,
+}
+
+impl Subscription {
+    /// Remove the listener now; equivalent to dropping the handle
+    pub fn unsubscribe(self) {}
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.borrow_mut().retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+/// Async stream of every tick from a [`CountdownTimer::stream`] call
+///
+/// Events are buffered, so a slow consumer sees every tick. The stream ends
+/// once the timer is dropped; dropping the stream unsubscribes.
+pub struct TickStream {
+    receiver: UnboundedReceiver<TickEvent>,
+    _subscription: Subscription,
+}
+
+impl Stream for TickStream {
+    type Item = TickEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TickEvent>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Upper bounds (exclusive) of the jitter histogram buckets in milliseconds;
+/// the last bucket of [`TimerStats::jitter_histogram`] counts everything above
+pub const JITTER_BUCKETS_MS: [f64; 6] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0];
+
+/// Timing diagnostics collected since the timer was last started
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimerStats {
+    /// Timeouts that fired while running
+    pub callbacks: usize,
+    /// Times the tick count was resynced with the clock
+    pub corrections: usize,
+    /// Largest difference between the tick count and the clock seen at any tick
+    pub max_drift_ms: f64,
+    /// Callbacks by how far they fired from their scheduled time, early or late
+    pub jitter_histogram: [usize; JITTER_BUCKETS_MS.len() + 1],
+    /// Times the clock was seen going backwards, e.g. a wall clock being set
+    pub clock_jumps: usize,
+}
+
+impl TimerStats {
+    fn record_jitter(&mut self, jitter_ms: f64) {
+        let jitter_ms = jitter_ms.abs();
+        let bucket = JITTER_BUCKETS_MS
+            .iter()
+            .position(|&limit| jitter_ms < limit)
+            .unwrap_or(JITTER_BUCKETS_MS.len());
+        self.jitter_histogram[bucket] += 1;
+    }
+}
+
+/// Everything needed to rebuild a timer after a page reload, with times in
+/// wall-clock milliseconds since the Unix epoch so they survive a new
+/// `performance.now()` origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimerSnapshot {
+    pub config: TimerConfig,
+    pub running: bool,
+    pub ticks_elapsed: usize,
+    /// Epoch time the tick count is measured from
+    pub start_epoch_ms: f64,
+    /// Time left until the next tick when paused, None if not paused
+    pub paused_remaining_ms: Option<f64>,
+    pub target_ticks: Option<usize>,
+    pub deadline_ms: Option<f64>,
+    /// Epoch time the snapshot was taken
+    pub taken_at_epoch_ms: f64,
+}
+
+impl TimerSnapshot {
+    /// Check the config and that every time is finite
+    pub fn validate(&self) -> Result<(), TimerConfigError> {
+        self.config.validate()?;
+        let times = [
+            Some(self.start_epoch_ms),
+            self.paused_remaining_ms,
+            self.deadline_ms,
+            Some(self.taken_at_epoch_ms),
+        ];
+        if times.into_iter().flatten().all(f64::is_finite) {
+            Ok(())
+        } else {
+            Err(TimerConfigError::NonFiniteSnapshotTime)
         }
     }
 }
 
-struct TimerState {
-    running: bool,
-    ticks_elapsed: usize,
+/// Tick timeline and drift correction, shared with [`crate::timer_group`]
+pub(crate) struct TimerState {
+    pub(crate) running: bool,
+    pub(crate) ticks_elapsed: usize,
     next_tick_time: f64,
     start_time: f64,
+    /// Time left until the next tick when paused, None if not paused
+    paused_remaining_ms: Option<f64>,
+    /// Tick count at which the timer completes, None to count forever
+    target_ticks: Option<usize>,
+    /// Epoch time in milliseconds at which the timer completes, None for no deadline
+    deadline_ms: Option<f64>,
+    /// Ticks skipped forward by the most recent drift correction
+    pub(crate) missed_ticks: usize,
+    /// Clock reading at the last start, resume or tick
+    last_now: f64,
+    pub(crate) stats: TimerStats,
+}
+
+impl TimerState {
+    pub(crate) fn idle() -> Self {
+        Self {
+            running: false,
+            ticks_elapsed: 0,
+            next_tick_time: 0.0,
+            start_time: 0.0,
+            paused_remaining_ms: None,
+            target_ticks: None,
+            deadline_ms: None,
+            missed_ticks: 0,
+            last_now: 0.0,
+            stats: TimerStats::default(),
+        }
+    }
+
+    pub(crate) fn begin(&mut self, config: &TimerConfig, now: f64) {
+        self.running = true;
+        self.ticks_elapsed = 0;
+        self.start_time = now;
+        self.next_tick_time = now + config.tick_ms();
+        self.paused_remaining_ms = None;
+        self.last_now = now;
+        self.stats = TimerStats::default();
+    }
+
+    fn pause(&mut self, config: &TimerConfig, now: f64) {
+        self.running = false;
+        let remaining = (self.next_tick_time - now).clamp(0.0, config.tick_ms());
+        self.paused_remaining_ms = Some(remaining);
+    }
+
+    /// Continue from a pause, shifting the baseline so the time spent
+    /// paused does not count as elapsed
+    fn resume(&mut self, config: &TimerConfig, now: f64) {
+        let remaining = self.paused_remaining_ms.take().unwrap_or_default();
+        self.running = true;
+        self.next_tick_time = now + remaining;
+        self.start_time = self.next_tick_time - (self.ticks_elapsed + 1) as f64 * config.tick_ms();
+        self.last_now = now;
+    }
+
+    /// Switch from `old` to `new` tick lengths, converting the tick count and
+    /// target so the elapsed time is unchanged. A partial tick in progress
+    /// carries over into the first tick of the new length.
+    fn set_interval(&mut self, old: &TimerConfig, new: &TimerConfig, now: f64) {
+        let old_ms = old.tick_ms();
+        let new_ms = new.tick_ms();
+        if let Some(target) = self.target_ticks {
+            self.target_ticks = Some((target as f64 * old_ms / new_ms).ceil() as usize);
+        }
+
+        let elapsed_ms = self.position_ms(old_ms, now);
+        self.ticks_elapsed = (elapsed_ms / new_ms).floor() as usize;
+        if let Some(target) = self.target_ticks {
+            self.ticks_elapsed = self.ticks_elapsed.min(target);
+        }
+        let next_tick_ms = (self.ticks_elapsed + 1) as f64 * new_ms;
+
+        if self.paused_remaining_ms.is_some() {
+            self.paused_remaining_ms = Some((next_tick_ms - elapsed_ms).clamp(0.0, new_ms));
+        } else if self.running {
+            self.start_time = now - elapsed_ms;
+            self.next_tick_time = self.start_time + next_tick_ms;
+            self.last_now = now;
+        }
+    }
+
+    fn snapshot(&self, config: &TimerConfig, now: f64, epoch_ms: f64) -> TimerSnapshot {
+        TimerSnapshot {
+            config: *config,
+            running: self.running,
+            ticks_elapsed: self.ticks_elapsed,
+            start_epoch_ms: self.start_time + (epoch_ms - now),
+            paused_remaining_ms: self.paused_remaining_ms,
+            target_ticks: self.target_ticks,
+            deadline_ms: self.deadline_ms,
+            taken_at_epoch_ms: epoch_ms,
+        }
+    }
+
+    /// Rebuild from `snapshot`, counting the ticks a running timer would have
+    /// seen while it was gone. Returns how many ticks that was.
+    fn restore(&mut self, snapshot: &TimerSnapshot, now: f64, epoch_ms: f64) -> usize {
+        let start_time = snapshot.start_epoch_ms - (epoch_ms - now);
+        *self = TimerState {
+            running: snapshot.running,
+            ticks_elapsed: snapshot.ticks_elapsed,
+            start_time,
+            paused_remaining_ms: snapshot.paused_remaining_ms,
+            target_ticks: snapshot.target_ticks,
+            deadline_ms: snapshot.deadline_ms,
+            last_now: now,
+            ..TimerState::idle()
+        };
+        if !self.running {
+            return 0;
+        }
+
+        let interval_ms = snapshot.config.tick_ms();
+        let expected_ticks = ((now - start_time) / interval_ms).floor().max(0.0) as usize;
+        self.ticks_elapsed = expected_ticks.max(snapshot.ticks_elapsed);
+        if let Some(target) = self.target_ticks {
+            self.ticks_elapsed = self.ticks_elapsed.min(target);
+        }
+        self.next_tick_time = start_time + (self.ticks_elapsed + 1) as f64 * interval_ms;
+        self.ticks_elapsed.saturating_sub(snapshot.ticks_elapsed)
+    }
+
+    /// Time since the start in milliseconds at `tick_ms` per tick, following
+    /// the clock while running even if the next tick is late
+    fn position_ms(&self, tick_ms: f64, now: f64) -> f64 {
+        if let Some(remaining) = self.paused_remaining_ms {
+            (self.ticks_elapsed + 1) as f64 * tick_ms - remaining
+        } else if self.running {
+            now - self.start_time
+        } else {
+            self.ticks_elapsed as f64 * tick_ms
+        }
+        .max(0.0)
+    }
+
+    /// Position between the last tick delivered and the next, for display
+    fn display_frame(&self, config: &TimerConfig, now: f64) -> DisplayFrame {
+        let tick_ms = config.tick_ms();
+        let last_ms = self.ticks_elapsed as f64 * tick_ms;
+        let next_ms = match self.target_ticks {
+            Some(target) if self.ticks_elapsed >= target => last_ms,
+            _ => last_ms + tick_ms,
+        };
+        let elapsed_ms = self.position_ms(tick_ms, now).clamp(last_ms, next_ms) * config.speed;
+        let progress = self.target_ticks.map(|target| {
+            let target_ms = target as f64 * f64::from(config.interval_ms);
+            if target_ms > 0.0 {
+                (elapsed_ms / target_ms).min(1.0)
+            } else {
+                1.0
+            }
+        });
+        DisplayFrame {
+            tick: self.ticks_elapsed,
+            elapsed_ms,
+            progress,
+        }
+    }
+
+    /// Move the whole timeline by `ms` without changing the tick count
+    fn shift(&mut self, ms: f64) {
+        self.start_time += ms;
+        self.next_tick_time += ms;
+    }
+
+    /// With `align_to_wall_clock`, shift a running timeline so its next tick
+    /// lands on the nearest whole interval of wall-clock time
+    pub(crate) fn align(&mut self, config: &TimerConfig, now: f64, epoch_ms: f64) {
+        if !config.align_to_wall_clock || !self.running {
+            return;
+        }
+        let interval_ms = config.tick_ms();
+        let next_epoch = self.next_tick_time + (epoch_ms - now);
+        let aligned = (next_epoch / interval_ms).round() * interval_ms;
+        self.shift(aligned - next_epoch);
+    }
+
+    /// Whole milliseconds until the next tick is due, rounded up so the
+    /// timeout is not set before the tick, and clamped at zero
+    pub(crate) fn delay_until_next(&self, now: f64) -> u32 {
+        (self.next_tick_time - now).max(0.0).ceil() as u32
+    }
+
+    /// The timeout for the tick reaching the target fired before the clock
+    /// got there. Counting it now would make the exact correction around the
+    /// target step back and repeat the previous tick, so wait for it instead.
+    /// A clock that went backwards is left to `advance`.
+    fn early_for_target(&self, config: &TimerConfig, now: f64) -> bool {
+        let next = self.ticks_elapsed + 1;
+        now >= self.last_now
+            && self.target_ticks.is_some_and(|target| next >= target)
+            && ((now - self.start_time) / config.tick_ms()).floor() < next as f64
+    }
+
+    /// Count one tick at `now`, resyncing with the clock every
+    /// `sync_interval_ticks` if drift exceeds `sync_threshold_ticks`.
+    /// Around the target any drift is corrected, so completion lands on the
+    /// first tick at or after the target time.
+    pub(crate) fn advance(&mut self, config: &TimerConfig, now: f64) -> TickEvent {
+        // A monotonic clock never goes backwards, so this is a wall clock
+        // being changed. Shift the baseline so this tick counts as on time
+        // rather than treating the jump as drift.
+        if now < self.last_now {
+            self.shift(now - self.next_tick_time);
+            self.stats.clock_jumps += 1;
+        }
+        self.last_now = now;
+
+        let scheduled_ms = self.next_tick_time;
+        self.stats.callbacks += 1;
+        self.stats.record_jitter(now - scheduled_ms);
+
+        self.ticks_elapsed += 1;
+        self.next_tick_time += config.tick_ms();
+        let counted = self.ticks_elapsed;
+
+        let elapsed_ms = now - self.start_time;
+        let drift_ms = elapsed_ms - self.ticks_elapsed as f64 * config.tick_ms();
+        self.stats.max_drift_ms = self.stats.max_drift_ms.max(drift_ms.abs());
+        let expected_ticks = (elapsed_ms / config.tick_ms()).floor() as usize;
+        let near_target = self
+            .target_ticks
+            .is_some_and(|target| self.ticks_elapsed >= target || expected_ticks >= target);
+
+        let threshold = if near_target {
+            Some(0)
+        } else if self
+            .ticks_elapsed
+            .is_multiple_of(config.sync_interval_ticks)
+        {
+            Some(config.sync_threshold_ticks)
+        } else {
+            None
+        };
+
+        let corrected = threshold
+            .is_some_and(|threshold| expected_ticks.abs_diff(self.ticks_elapsed) > threshold);
+        if corrected {
+            self.stats.corrections += 1;
+            self.ticks_elapsed = expected_ticks;
+            self.next_tick_time =
+                self.start_time + (expected_ticks as f64 * config.tick_ms()) + config.tick_ms();
+        }
+
+        if let Some(target) = self.target_ticks {
+            self.ticks_elapsed = self.ticks_elapsed.min(target);
+        }
+        self.missed_ticks = self.ticks_elapsed.saturating_sub(counted);
+
+        TickEvent {
+            tick: self.ticks_elapsed,
+            interval_ms: config.interval_ms,
+            scheduled_ms,
+            actual_ms: now,
+            drift_ms,
+            corrected,
+            missed_ticks: match config.catch_up {
+                CatchUpPolicy::Coalesce => self.missed_ticks,
+                CatchUpPolicy::Skip | CatchUpPolicy::Burst => 0,
+            },
+            remaining_ms: None,
+        }
+    }
+
+    /// Jump the tick count straight to the clock, whatever the sync interval,
+    /// e.g. after timeouts were throttled in a hidden tab
+    fn resync(&mut self, config: &TimerConfig, now: f64) -> TickEvent {
+        self.last_now = now;
+        let elapsed_ms = now - self.start_time;
+        let drift_ms = elapsed_ms - self.ticks_elapsed as f64 * config.tick_ms();
+        let mut expected_ticks = (elapsed_ms / config.tick_ms()).floor().max(0.0) as usize;
+        if let Some(target) = self.target_ticks {
+            expected_ticks = expected_ticks.min(target);
+        }
+
+        // As in `advance`, the event itself counts one of the ticks jumped
+        let jump = expected_ticks.saturating_sub(self.ticks_elapsed);
+        self.missed_ticks = jump.saturating_sub(1);
+        if jump > 0 {
+            self.stats.corrections += 1;
+            self.ticks_elapsed = expected_ticks;
+            self.next_tick_time = self.start_time + (expected_ticks + 1) as f64 * config.tick_ms();
+        }
+
+        TickEvent {
+            tick: self.ticks_elapsed,
+            interval_ms: config.interval_ms,
+            scheduled_ms: now,
+            actual_ms: now,
+            drift_ms,
+            corrected: jump > 0,
+            missed_ticks: match config.catch_up {
+                CatchUpPolicy::Coalesce => self.missed_ticks,
+                CatchUpPolicy::Skip | CatchUpPolicy::Burst => 0,
+            },
+            remaining_ms: None,
+        }
+    }
+
+    /// Wall-clock time left until the deadline, clamped at zero
+    fn remaining_ms(&self, epoch_ms: f64) -> Option<f64> {
+        self.deadline_ms
+            .map(|deadline| (deadline - epoch_ms).max(0.0))
+    }
+
+    fn is_complete(&self, epoch_ms: f64) -> bool {
+        self.target_ticks
+            .is_some_and(|target| self.ticks_elapsed >= target)
+            || self.remaining_ms(epoch_ms) == Some(0.0)
+    }
 }
 
 /// CountdownTimer must be wrapped in Rc for the recursive callback to work
+///
+/// Time is read from a [`Clock`] and ticks are deferred through a [`Scheduler`];
+/// `new` uses the browser for both, `with_backend` accepts any pair.
+///
+/// Any method may be called from `on_tick`, a subscriber or `on_complete`,
+/// and takes effect immediately, in the order called. The tick being
+/// delivered still reaches every listener. If a callback starts, stops,
+/// pauses, resumes, resets, restores or resyncs the running timer, or
+/// changes its interval or deadline, the rest of that tick is abandoned: no
+/// further [`CatchUpPolicy::Burst`] replays, no `on_complete` and no
+/// rescheduling beyond what the callback's own commands set up.
+///
+/// Pending ticks only hold a weak reference, so the timer runs exactly as
+/// long as some `Rc` to it is alive. Dropping the last one stops it and
+/// cancels the pending timeout. To stop at the end of a scope while other
+/// clones live on, hold the [`RunGuard`] from
+/// [`start_guarded`](Self::start_guarded) instead of calling `stop` yourself.
 pub struct CountdownTimer<F>
 where
-    F: Fn(usize) + 'static,
+    F: Fn(TickEvent) + 'static,
 {
-    config: TimerConfig,
+    config: Cell<TimerConfig>,
+    clock: Rc<dyn Clock>,
+    scheduler: Rc<dyn Scheduler>,
     state: Rc<RefCell<TimerState>>,
-    timeout_handle: Rc<Cell<Option<Timeout>>>,
+    timeout_handle: Rc<Cell<Option<TaskHandle>>>,
     on_tick: Rc<F>,
+    on_complete: RefCell<Option<Rc<dyn Fn()>>>,
+    subscribers: Rc<Subscribers>,
+    next_subscriber_id: Cell<u64>,
+    /// Bumped by every command that changes whether or where the timer runs,
+    /// so a tick being delivered can tell a callback took control
+    generation: Cell<u64>,
 }
 
 impl<F> CountdownTimer<F>
 where
-    F: Fn(usize) + 'static,
+    F: Fn(TickEvent) + 'static,
 {
+    /// Create a timer driven by the browser's monotonic `performance.now()`
+    /// clock and `setTimeout`
     pub fn new(config: TimerConfig, on_tick: F) -> Rc<Self> {
-        Rc::new(Self {
+        Self::with_clock(config, Rc::new(PerformanceClock), on_tick)
+    }
+
+    /// Like `new`, but rejects an invalid `config`
+    pub fn try_new(config: TimerConfig, on_tick: F) -> Result<Rc<Self>, TimerConfigError> {
+        config.validate()?;
+        Ok(Self::new(config, on_tick))
+    }
+
+    /// Create a browser timer reading time from `clock`
+    pub fn with_clock(config: TimerConfig, clock: Rc<dyn Clock>, on_tick: F) -> Rc<Self> {
+        Self::with_backend(config, clock, Rc::new(BrowserScheduler), on_tick)
+    }
+
+    /// Like `new`, but with timeouts running in a dedicated Web Worker so
+    /// ticks stay on time while the main thread is busy, see
+    /// [`WorkerScheduler`]. Fails if the worker cannot be started, e.g. when
+    /// a content security policy blocks `blob:` workers; use `new` then.
+    pub fn new_in_worker(config: TimerConfig, on_tick: F) -> Result<Rc<Self>, JsValue> {
+        let scheduler = WorkerScheduler::new()?;
+        Ok(Self::with_backend(
             config,
-            state: Rc::new(RefCell::new(TimerState {
-                running: false,
-                ticks_elapsed: 0,
-                next_tick_time: 0.0,
-                start_time: 0.0,
-            })),
+            Rc::new(PerformanceClock),
+            Rc::new(scheduler),
+            on_tick,
+        ))
+    }
+
+    /// Create a timer reading time from `clock` and deferring ticks through `scheduler`
+    pub fn with_backend(
+        config: TimerConfig,
+        clock: Rc<dyn Clock>,
+        scheduler: Rc<dyn Scheduler>,
+        on_tick: F,
+    ) -> Rc<Self> {
+        Rc::new(Self {
+            config: Cell::new(config),
+            clock,
+            scheduler,
+            state: Rc::new(RefCell::new(TimerState::idle())),
             timeout_handle: Rc::new(Cell::new(None)),
             on_tick: Rc::new(on_tick),
+            on_complete: RefCell::new(None),
+            subscribers: Rc::new(RefCell::new(Vec::new())),
+            next_subscriber_id: Cell::new(0),
+            generation: Cell::new(0),
         })
     }
 
-    pub fn start(self: &Rc<Self>) {
-        let mut state = self.state.borrow_mut();
-        if state.running {
-            return;
+    /// Add a listener that receives every tick after the main callback,
+    /// in subscription order, until the returned handle is dropped
+    pub fn subscribe(&self, listener: impl Fn(TickEvent) + 'static) -> Subscription {
+        let id = self.next_subscriber_id.get();
+        self.next_subscriber_id.set(id + 1);
+        self.subscribers.borrow_mut().push((id, Rc::new(listener)));
+        Subscription {
+            id,
+            subscribers: Rc::downgrade(&self.subscribers),
+        }
+    }
+
+    /// Ticks as an async [`Stream`], for use with `wasm_bindgen_futures::spawn_local`
+    /// or any native executor
+    pub fn stream(&self) -> TickStream {
+        let (sender, receiver) = unbounded();
+        let subscription = self.subscribe(move |event| {
+            // Nothing to do if the stream was dropped mid-dispatch
+            let _ = sender.unbounded_send(event);
+        });
+        TickStream {
+            receiver,
+            _subscription: subscription,
         }
+    }
 
-        let now = Date::now();
-        state.running = true;
-        state.ticks_elapsed = 0;
-        state.start_time = now;
-        state.next_tick_time = now + self.config.interval_ms as f64;
-        drop(state);
+    /// Call `listener` whenever the drift-corrected timeline crosses a whole
+    /// multiple of `period_ms`, with the number of whole periods elapsed.
+    /// A correction that jumps over several boundaries fires once with the
+    /// latest count; under [`CatchUpPolicy::Burst`] each boundary fires.
+    pub fn every_ms(
+        &self,
+        period_ms: u64,
+        listener: impl Fn(usize, TickEvent) + 'static,
+    ) -> Subscription {
+        let period_ms = period_ms.max(1);
+        let last_boundary = Cell::new(0);
+        self.subscribe(move |event| {
+            let boundary = event.tick as u64 * u64::from(event.interval_ms) / period_ms;
+            // Restarts and backward corrections move the boundary down
+            let previous = last_boundary.replace(boundary);
+            if boundary > previous {
+                listener(boundary as usize, event);
+            }
+        })
+    }
 
-        self.schedule_tick();
+    /// Call `listener` every `ticks` ticks with the number of periods elapsed.
+    /// The period is fixed in milliseconds at the current interval, so it is
+    /// not rescaled by a later [`set_interval`](Self::set_interval).
+    pub fn every_ticks(
+        &self,
+        ticks: usize,
+        listener: impl Fn(usize, TickEvent) + 'static,
+    ) -> Subscription {
+        self.every_ms(
+            ticks as u64 * u64::from(self.config.get().interval_ms),
+            listener,
+        )
     }
 
-    pub fn stop(&self) {
-        let mut state = self.state.borrow_mut();
-        state.running = false;
-        drop(state);
+    /// Call `listener` on each whole second with the seconds elapsed
+    pub fn on_second(&self, listener: impl Fn(usize, TickEvent) + 'static) -> Subscription {
+        self.every_ms(1000, listener)
+    }
 
-        if let Some(handle) = self.timeout_handle.take() {
-            handle.cancel();
+    /// Call `listener` on each whole minute with the minutes elapsed
+    pub fn on_minute(&self, listener: impl Fn(usize, TickEvent) + 'static) -> Subscription {
+        self.every_ms(60_000, listener)
+    }
+
+    fn emit(&self, event: TickEvent) {
+        (self.on_tick)(event);
+        // Snapshot so listeners can subscribe or unsubscribe while being called
+        let subscribers: Vec<_> = self
+            .subscribers
+            .borrow()
+            .iter()
+            .map(|(_, listener)| Rc::clone(listener))
+            .collect();
+        for listener in subscribers {
+            listener(event);
         }
     }
 
-    pub fn reset(&self) {
+    /// Count down `target_ticks` from each start, then stop and call
+    /// `on_complete` once. The final tick is reported as `target_ticks`.
+    pub fn set_target(&self, target_ticks: usize, on_complete: impl Fn() + 'static) {
+        self.state.borrow_mut().target_ticks = Some(target_ticks);
+        *self.on_complete.borrow_mut() = Some(Rc::new(on_complete));
+    }
+
+    /// Complete at `deadline_epoch_ms`, milliseconds since the Unix epoch as
+    /// returned by `Date.now()`, then stop and call `on_complete` once.
+    /// Time left is recomputed from [`Clock::epoch_ms`] on every tick, so
+    /// sleep or throttling never accumulates. A deadline falling between
+    /// ticks calls `on_complete` at the deadline without a tick event, so
+    /// the tick count only ever increases.
+    pub fn set_deadline(self: &Rc<Self>, deadline_epoch_ms: f64, on_complete: impl Fn() + 'static) {
         let mut state = self.state.borrow_mut();
-        state.running = false;
-        state.ticks_elapsed = 0;
-        state.next_tick_time = 0.0;
-        state.start_time = 0.0;
+        state.deadline_ms = Some(deadline_epoch_ms);
+        let running = state.running;
         drop(state);
+        *self.on_complete.borrow_mut() = Some(Rc::new(on_complete));
 
-        if let Some(handle) = self.timeout_handle.take() {
-            handle.cancel();
+        // The pending tick may be due after the new deadline
+        if running {
+            self.interrupt();
+            if let Some(handle) = self.timeout_handle.take() {
+                handle.cancel();
+            }
+            self.schedule_tick();
         }
     }
 
-    pub fn elapsed_ticks(&self) -> usize {
-        self.state.borrow().ticks_elapsed
+    /// Wall-clock time left until the deadline, None if no deadline is set
+    pub fn remaining_ms(&self) -> Option<f64> {
+        self.state.borrow().remaining_ms(self.clock.epoch_ms())
     }
 
-    pub fn is_running(&self) -> bool {
-        self.state.borrow().running
+    /// Drop any target or deadline and go back to counting up forever
+    pub fn clear_target(&self) {
+        let mut state = self.state.borrow_mut();
+        state.target_ticks = None;
+        state.deadline_ms = None;
+        drop(state);
+        self.on_complete.borrow_mut().take();
     }
 
-    fn schedule_tick(self: &Rc<Self>) {
-        let delay = {
-            let state = self.state.borrow();
-            if !state.running {
-                return;
-            }
-            let now = Date::now();
-            (state.next_tick_time - now).max(0.0).round() as u32
-        };
+    /// Ticks left until the target, None if no target is set
+    pub fn remaining(&self) -> Option<usize> {
+        let state = self.state.borrow();
+        state
+            .target_ticks
+            .map(|target| target.saturating_sub(state.ticks_elapsed))
+    }
 
-        let state_clone = Rc::clone(&self.state);
-        let config = self.config;
-        let on_tick_clone = Rc::clone(&self.on_tick);
-        let timeout_handle_clone = Rc::clone(&self.timeout_handle);
-        let timer_clone = Rc::clone(self);
+    pub fn start(self: &Rc<Self>) {
+        let mut state = self.state.borrow_mut();
+        if state.running {
+            return;
+        }
 
-        let handle = Timeout::new(delay, move || {
-            timeout_handle_clone.take();
+        self.interrupt();
+        state.begin(&self.config.get(), self.clock.now());
+        self.align(&mut state);
+        drop(state);
 
-            let mut state = state_clone.borrow_mut();
-            if !state.running {
-                return;
-            }
+        self.schedule_tick();
+    }
 
-            let now = Date::now();
-            state.ticks_elapsed += 1;
-            state.next_tick_time += config.interval_ms as f64;
+    /// Start the timer and stop it again when the returned guard is dropped,
+    /// even if other clones of the `Rc` are still around
+    pub fn start_guarded(self: &Rc<Self>) -> RunGuard<F> {
+        self.start();
+        RunGuard {
+            timer: Rc::clone(self),
+        }
+    }
 
-            if state
-                .ticks_elapsed
-                .is_multiple_of(config.sync_interval_ticks)
-            {
-                let elapsed_ms = now - state.start_time;
-                let expected_ticks = (elapsed_ms / config.interval_ms as f64).floor() as usize;
-                let tick_diff = expected_ticks.abs_diff(state.ticks_elapsed);
-
-                if tick_diff > config.sync_threshold_ticks {
-                    state.ticks_elapsed = expected_ticks;
-                    state.next_tick_time = state.start_time
-                        + (expected_ticks as f64 * config.interval_ms as f64)
-                        + config.interval_ms as f64;
-                }
-            }
+    /// Start on the next whole multiple of `period_ms` in wall-clock time,
+    /// e.g. 1000 for the next full second or 60_000 for the next full minute.
+    /// Until then the timer is not running; `stop` cancels the pending start.
+    pub fn start_at_next(self: &Rc<Self>, period_ms: u64) {
+        if self.state.borrow().running {
+            return;
+        }
 
-            let ticks = state.ticks_elapsed;
-            drop(state);
+        let period_ms = period_ms.max(1) as f64;
+        let delay_ms = period_ms - self.clock.epoch_ms().rem_euclid(period_ms);
+        let start_time = self.clock.now() + delay_ms;
 
-            (on_tick_clone)(ticks);
-            timer_clone.schedule_tick();
-        });
+        let timer_weak = Rc::downgrade(self);
+        let handle = self.scheduler.schedule(
+            delay_ms.round().min(f64::from(u32::MAX)) as u32,
+            Box::new(move || {
+                let Some(timer_clone) = timer_weak.upgrade() else {
+                    return;
+                };
+                timer_clone.timeout_handle.take();
 
+                let mut state = timer_clone.state.borrow_mut();
+                if state.running {
+                    return;
+                }
+                timer_clone.interrupt();
+                state.begin(&timer_clone.config.get(), timer_clone.clock.now());
+                let offset = start_time - state.start_time;
+                state.shift(offset);
+                timer_clone.align(&mut state);
+                drop(state);
+
+                timer_clone.schedule_tick();
+            }),
+        );
         self.timeout_handle.set(Some(handle));
     }
-}
 
-impl<F> Drop for CountdownTimer<F>
+    /// Mark that a command took control of the timer, see [`dispatch`](Self::dispatch)
+    fn interrupt(&self) {
+        self.generation.set(self.generation.get() + 1);
+    }
+
+    /// With `align_to_wall_clock`, shift a running timeline so its next tick
+    /// lands on the nearest whole interval of wall-clock time
+    fn align(&self, state: &mut TimerState) {
+        state.align(&self.config.get(), self.clock.now(), self.clock.epoch_ms());
+    }
+
+    /// Stop ticking but keep the elapsed tick count so `resume` can continue it
+    pub fn pause(&self) {
+        let mut state = self.state.borrow_mut();
+        if !state.running {
+            return;
+        }
+        self.interrupt();
+        state.pause(&self.config.get(), self.clock.now());
+        drop(state);
+
+        if let Some(handle) = self.timeout_handle.take() {
+            handle.cancel();
+        }
+    }
+
+    /// Continue a paused timer; the next tick fires after whatever was left
+    /// of the interval in progress when it was paused
+    pub fn resume(self: &Rc<Self>) {
+        let mut state = self.state.borrow_mut();
+        if state.running || state.paused_remaining_ms.is_none() {
+            return;
+        }
+        self.interrupt();
+        state.resume(&self.config.get(), self.clock.now());
+        self.align(&mut state);
+        drop(state);
+
+        self.schedule_tick();
+    }
+
+    pub fn stop(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.running {
+            self.interrupt();
+        }
+        state.running = false;
+        state.paused_remaining_ms = None;
+        drop(state);
+
+        if let Some(handle) = self.timeout_handle.take() {
+            handle.cancel();
+        }
+    }
+
+    pub fn reset(&self) {
+        self.interrupt();
+        let mut state = self.state.borrow_mut();
+        *state = TimerState {
+            target_ticks: state.target_ticks,
+            deadline_ms: state.deadline_ms,
+            ..TimerState::idle()
+        };
+        drop(state);
+
+        if let Some(handle) = self.timeout_handle.take() {
+            handle.cancel();
+        }
+    }
+
+    pub fn config(&self) -> TimerConfig {
+        self.config.get()
+    }
+
+    /// Change the tick length without restarting, e.g. from tenths to whole
+    /// seconds when the display stops showing tenths. The elapsed time is
+    /// kept: the tick count and any target are converted to the new length
+    /// and a running timer reschedules its next tick on the same timeline.
+    pub fn set_interval(self: &Rc<Self>, interval_ms: u32) -> Result<(), TimerConfigError> {
+        let old = self.config.get();
+        let new = TimerConfig { interval_ms, ..old };
+        new.validate()?;
+        self.config.set(new);
+
+        let mut state = self.state.borrow_mut();
+        state.set_interval(&old, &new, self.clock.now());
+        self.align(&mut state);
+        let running = state.running;
+        drop(state);
+
+        if running {
+            self.interrupt();
+            if let Some(handle) = self.timeout_handle.take() {
+                handle.cancel();
+            }
+            self.schedule_tick();
+        }
+        Ok(())
+    }
+
+    /// Capture the timer's state, e.g. to save in `localStorage` across a reload
+    pub fn snapshot(&self) -> TimerSnapshot {
+        self.state
+            .borrow()
+            .snapshot(&self.config.get(), self.clock.now(), self.clock.epoch_ms())
+    }
+
+    /// Replace the timer's state with `snapshot`. A timer that was running
+    /// picks up where the wall clock says it would be now and returns the
+    /// ticks that passed while it was gone; paused or stopped timers return 0.
+    /// Callbacks and subscribers are not part of a snapshot and stay as
+    /// registered on this timer. A snapshot that fails
+    /// [`validate`](TimerSnapshot::validate), e.g. edited in storage, is
+    /// rejected and leaves the timer untouched.
+    pub fn restore(self: &Rc<Self>, snapshot: &TimerSnapshot) -> Result<usize, TimerConfigError> {
+        snapshot.validate()?;
+        self.interrupt();
+        if let Some(handle) = self.timeout_handle.take() {
+            handle.cancel();
+        }
+        self.config.set(snapshot.config);
+
+        let mut state = self.state.borrow_mut();
+        let passed = state.restore(snapshot, self.clock.now(), self.clock.epoch_ms());
+        drop(state);
+
+        self.schedule_tick();
+        Ok(passed)
+    }
+
+    pub fn elapsed_ticks(&self) -> usize {
+        self.state.borrow().ticks_elapsed
+    }
+
+    /// Time counted so far, before `speed` scaling; moves in whole ticks
+    pub fn elapsed(&self) -> Duration {
+        self.config.get().ticks_to_duration(self.elapsed_ticks())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.borrow().running
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused_remaining_ms.is_some()
+    }
+
+    /// Ticks folded into the most recent callback by a
+    /// [`CatchUpPolicy::Coalesce`] correction; always 0 for other policies
+    pub fn missed_ticks(&self) -> usize {
+        match self.config.get().catch_up {
+            CatchUpPolicy::Coalesce => self.state.borrow().missed_ticks,
+            CatchUpPolicy::Skip | CatchUpPolicy::Burst => 0,
+        }
+    }
+
+    /// Drift and jitter diagnostics since the last start
+    pub fn stats(&self) -> TimerStats {
+        self.state.borrow().stats.clone()
+    }
+
+    /// Where the timer is right now, interpolated between ticks for smooth
+    /// display; round logic should stay on the tick callbacks
+    pub fn display_frame(&self) -> DisplayFrame {
+        self.state
+            .borrow()
+            .display_frame(&self.config.get(), self.clock.now())
+    }
+
+    /// Call `on_frame` with [`display_frame`](Self::display_frame) before
+    /// every repaint, via `requestAnimationFrame` in the browser, until the
+    /// returned loop or the timer is dropped. Frames keep coming while the
+    /// timer is paused or stopped, so a restart needs no new loop.
+    pub fn animate(self: &Rc<Self>, on_frame: impl Fn(DisplayFrame) + 'static) -> AnimationLoop {
+        let frames = Rc::new(FrameLoop {
+            pending: Cell::new(None),
+            on_frame: Box::new(on_frame),
+        });
+        self.request_frame(&frames);
+        AnimationLoop { _frames: frames }
+    }
+
+    fn request_frame(self: &Rc<Self>, frames: &Rc<FrameLoop>) {
+        let timer_weak = Rc::downgrade(self);
+        let frames_weak = Rc::downgrade(frames);
+        let handle = self.scheduler.schedule_frame(Box::new(move || {
+            let (Some(timer), Some(frames)) = (timer_weak.upgrade(), frames_weak.upgrade()) else {
+                return;
+            };
+            frames.pending.take();
+            (frames.on_frame)(timer.display_frame());
+            timer.request_frame(&frames);
+        }));
+        frames.pending.set(Some(handle));
+    }
+
+    /// Catch up with the clock now instead of on the next sync tick. If the
+    /// timer fell behind, the missed ticks are delivered at once according to
+    /// the catch-up policy. Returns None if the timer is not running.
+    pub fn resync(self: &Rc<Self>) -> Option<Resync> {
+        let config = self.config.get();
+        let now = self.clock.now();
+        let epoch_ms = self.clock.epoch_ms();
+
+        let mut state = self.state.borrow_mut();
+        if !state.running {
+            return None;
+        }
+        let gap_ms = now - state.last_now;
+        let ticks_before = state.ticks_elapsed;
+        let mut event = state.resync(&config, now);
+        event.remaining_ms = state.remaining_ms(epoch_ms);
+        let missed_ticks = event.tick - ticks_before;
+        let first_tick = event.tick - state.missed_ticks;
+        let complete = state.is_complete(epoch_ms);
+        if complete {
+            state.running = false;
+        }
+        drop(state);
+
+        if missed_ticks > 0 || complete {
+            self.interrupt();
+            if let Some(handle) = self.timeout_handle.take() {
+                handle.cancel();
+            }
+            if missed_ticks > 0 {
+                self.dispatch(&config, event, first_tick, complete);
+            } else {
+                self.complete();
+            }
+        }
+        Some(Resync {
+            gap_ms,
+            missed_ticks,
+        })
+    }
+
+    /// Call [`resync`](Self::resync) whenever the page becomes visible again,
+    /// passing what it found to `on_resync`. Hidden tabs throttle timeouts
+    /// heavily, so without this the timer only catches up on its next sync
+    /// tick. The listener is removed when the handle is dropped. Returns None
+    /// without a browser document, e.g. in a worker or a native build.
+    pub fn resync_when_visible(
+        self: &Rc<Self>,
+        on_resync: impl Fn(Resync) + 'static,
+    ) -> Option<VisibilityListener> {
+        // web-sys imports panic when touched outside wasm
+        if !cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        let document = web_sys::window()?.document()?;
+        let timer = Rc::downgrade(self);
+        let listener = EventListener::new(&document.clone(), "visibilitychange", move |_| {
+            if document.visibility_state() != VisibilityState::Visible {
+                return;
+            }
+            if let Some(resync) = timer.upgrade().and_then(|timer| timer.resync()) {
+                on_resync(resync);
+            }
+        });
+        Some(VisibilityListener {
+            _listener: listener,
+        })
+    }
+
+    fn schedule_tick(self: &Rc<Self>) {
+        let delay = {
+            let state = self.state.borrow();
+            if !state.running {
+                return;
+            }
+            let delay = state.delay_until_next(self.clock.now());
+            match state.remaining_ms(self.clock.epoch_ms()) {
+                Some(remaining) => delay.min(remaining.ceil() as u32),
+                None => delay,
+            }
+        };
+
+        let state_clone = Rc::clone(&self.state);
+        let clock = Rc::clone(&self.clock);
+        let timeout_handle_clone = Rc::clone(&self.timeout_handle);
+        // Only a weak reference, so a pending tick never keeps the timer alive
+        let timer_weak = Rc::downgrade(self);
+
+        let handle = self.scheduler.schedule(
+            delay,
+            Box::new(move || {
+                timeout_handle_clone.take();
+                let Some(timer_clone) = timer_weak.upgrade() else {
+                    return;
+                };
+
+                let mut state = state_clone.borrow_mut();
+                if !state.running {
+                    return;
+                }
+
+                let config = timer_clone.config.get();
+                let now = clock.now();
+                let epoch_ms = clock.epoch_ms();
+                let remaining_ms = state.remaining_ms(epoch_ms);
+                let mut event = if remaining_ms == Some(0.0) && now < state.next_tick_time {
+                    // The deadline passed between ticks, there is no tick to report
+                    state.last_now = now;
+                    state.running = false;
+                    drop(state);
+                    timer_clone.complete();
+                    return;
+                } else if (remaining_ms.is_some() && now < state.next_tick_time)
+                    || state.early_for_target(&config, now)
+                {
+                    // A timeout shortened for the deadline can still fire
+                    // early when the two clocks disagree, so wait it out
+                    drop(state);
+                    timer_clone.schedule_tick();
+                    return;
+                } else {
+                    state.advance(&config, now)
+                };
+                event.remaining_ms = remaining_ms;
+                let first_tick = event.tick - state.missed_ticks;
+                let complete = state.is_complete(epoch_ms);
+                if complete {
+                    state.running = false;
+                }
+                drop(state);
+
+                timer_clone.dispatch(&config, event, first_tick, complete);
+            }),
+        );
+
+        self.timeout_handle.set(Some(handle));
+    }
+
+    /// Deliver `event`, replaying from `first_tick` under
+    /// [`CatchUpPolicy::Burst`], then complete or schedule the next tick.
+    /// Stops short once a callback takes control of the timer.
+    fn dispatch(
+        self: &Rc<Self>,
+        config: &TimerConfig,
+        event: TickEvent,
+        first_tick: usize,
+        complete: bool,
+    ) {
+        let generation = self.generation.get();
+        let interrupted = || self.generation.get() != generation;
+        match config.catch_up {
+            CatchUpPolicy::Burst => {
+                for tick in first_tick..=event.tick {
+                    if interrupted() {
+                        return;
+                    }
+                    self.emit(TickEvent { tick, ..event });
+                }
+            }
+            CatchUpPolicy::Skip | CatchUpPolicy::Coalesce => self.emit(event),
+        }
+        if interrupted() {
+            return;
+        }
+        if complete {
+            self.complete();
+        } else {
+            self.schedule_tick();
+        }
+    }
+
+    fn complete(&self) {
+        let on_complete = self.on_complete.borrow().clone();
+        if let Some(on_complete) = on_complete {
+            on_complete();
+        }
+    }
+}
+
+impl<F> Drop for CountdownTimer<F>
 where
-    F: Fn(usize) + 'static,
+    F: Fn(TickEvent) + 'static,
 {
     fn drop(&mut self) {
         self.stop();
     }
 }
+
+/// Keeps a timer from [`CountdownTimer::start_guarded`] running until dropped
+///
+/// Derefs to the timer, so it can still be paused, resumed or inspected.
+#[must_use = "dropping the guard stops the timer"]
+pub struct RunGuard<F>
+where
+    F: Fn(TickEvent) + 'static,
+{
+    timer: Rc<CountdownTimer<F>>,
+}
+
+impl<F> Deref for RunGuard<F>
+where
+    F: Fn(TickEvent) + 'static,
+{
+    type Target = Rc<CountdownTimer<F>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.timer
+    }
+}
+
+impl<F> Drop for RunGuard<F>
+where
+    F: Fn(TickEvent) + 'static,
+{
+    fn drop(&mut self) {
+        self.timer.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::simulation::Simulation;
+    use std::rc::Weak;
+
+    fn started(config: &TimerConfig, clock: &ManualClock) -> TimerState {
+        let mut state = TimerState::idle();
+        state.begin(config, clock.now());
+        state
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        assert_eq!(TimerConfig::tenths().validate(), Ok(()));
+        assert_eq!(TimerConfig::seconds().validate(), Ok(()));
+        assert_eq!(TimerConfig::seconds().interval_ms, 1000);
+        assert_eq!(TimerConfig::default().interval_ms, 100);
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let config = TimerConfig::builder()
+            .interval_ms(250)
+            .sync_interval_ticks(4)
+            .sync_threshold_ticks(2)
+            .catch_up(CatchUpPolicy::Burst)
+            .build()
+            .unwrap();
+        assert_eq!(config.interval_ms, 250);
+        assert_eq!(config.sync_interval_ticks, 4);
+        assert_eq!(config.sync_threshold_ticks, 2);
+        assert_eq!(config.catch_up, CatchUpPolicy::Burst);
+
+        assert_eq!(
+            TimerConfig::builder().interval_ms(0).build().unwrap_err(),
+            TimerConfigError::ZeroInterval
+        );
+        assert_eq!(
+            TimerConfig::builder()
+                .interval_ms(u32::MAX)
+                .build()
+                .unwrap_err(),
+            TimerConfigError::IntervalTooLong(u32::MAX)
+        );
+        assert_eq!(
+            TimerConfig::builder()
+                .sync_interval_ticks(0)
+                .build()
+                .unwrap_err(),
+            TimerConfigError::ZeroSyncInterval
+        );
+        assert_eq!(
+            TimerConfig::builder()
+                .sync_threshold_ticks(0)
+                .build()
+                .unwrap_err(),
+            TimerConfigError::ZeroSyncThreshold
+        );
+    }
+
+    #[test]
+    fn test_duration_conversions() {
+        let config = TimerConfig::builder()
+            .interval(Duration::from_millis(250))
+            .build()
+            .unwrap();
+        assert_eq!(config.interval_ms, 250);
+        assert_eq!(config.interval(), Duration::from_millis(250));
+        assert_eq!(config.ticks_to_duration(6), Duration::from_millis(1500));
+        assert_eq!(config.duration_to_ticks(Duration::from_millis(1500)), 6);
+        assert_eq!(config.duration_to_ticks(Duration::from_millis(1501)), 7);
+        assert_eq!(config.duration_to_ticks(Duration::ZERO), 0);
+
+        // Sub-millisecond precision is dropped, leaving an invalid interval
+        assert_eq!(
+            TimerConfig::builder()
+                .interval(Duration::from_micros(500))
+                .build()
+                .unwrap_err(),
+            TimerConfigError::ZeroInterval
+        );
+        assert_eq!(
+            TimerConfig::builder()
+                .interval(Duration::from_secs(u64::MAX))
+                .build()
+                .unwrap_err(),
+            TimerConfigError::IntervalTooLong(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_elapsed_duration() {
+        let sim = Simulation::new();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let events_clone = Rc::clone(&events);
+        let config = TimerConfig::builder().speed(2.0).build().unwrap();
+        let timer = sim.timer(config, move |event| {
+            events_clone.borrow_mut().push(event.elapsed());
+        });
+        timer.set_target(config.duration_to_ticks(Duration::from_secs(1)), || {});
+
+        timer.start();
+        sim.advance(125.0);
+        assert_eq!(timer.elapsed(), Duration::from_millis(200));
+        assert_eq!(
+            *events.borrow(),
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+
+        timer.set_interval(50).unwrap();
+        assert_eq!(timer.elapsed(), Duration::from_millis(250));
+        sim.advance(1000.0);
+        assert_eq!(timer.elapsed(), Duration::from_secs(1));
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_config() {
+        let config = TimerConfig {
+            interval_ms: 0,
+            ..TimerConfig::default()
+        };
+        let err = CountdownTimer::try_new(config, |_| {}).err().unwrap();
+        assert_eq!(err, TimerConfigError::ZeroInterval);
+        assert_eq!(err.to_string(), "interval_ms must be greater than 0");
+    }
+
+    #[test]
+    fn test_on_time_ticks_count_up() {
+        let config = TimerConfig::default();
+        let clock = ManualClock::new(5000.0);
+        let mut state = started(&config, &clock);
+
+        for expected in 1..=25 {
+            clock.advance(100.0);
+            assert_eq!(state.advance(&config, clock.now()).tick, expected);
+            assert_eq!(state.delay_until_next(clock.now()), 100);
+        }
+    }
+
+    #[test]
+    fn test_drift_within_threshold_is_not_corrected() {
+        let config = TimerConfig::default();
+        let clock = ManualClock::new(0.0);
+        let mut state = started(&config, &clock);
+
+        // Each timeout fires 15ms late: 10 ticks land at 1150ms, 1 tick behind
+        for _ in 0..10 {
+            clock.advance(115.0);
+            state.advance(&config, clock.now());
+        }
+        assert_eq!(state.ticks_elapsed, 10);
+        assert_eq!(state.next_tick_time, 1100.0);
+    }
+
+    #[test]
+    fn test_drift_beyond_threshold_is_corrected_on_sync_tick() {
+        let config = TimerConfig::default();
+        let clock = ManualClock::new(0.0);
+        let mut state = started(&config, &clock);
+
+        // Nine timeouts on time, then the tenth fires 300ms late
+        for _ in 0..9 {
+            clock.advance(100.0);
+            state.advance(&config, clock.now());
+        }
+        clock.advance(400.0);
+        assert_eq!(state.advance(&config, clock.now()).tick, 13);
+        assert_eq!(state.next_tick_time, 1400.0);
+        assert_eq!(state.delay_until_next(clock.now()), 100);
+    }
+
+    #[test]
+    fn test_tick_event_reports_timing() {
+        let config = TimerConfig::default();
+        let clock = ManualClock::new(0.0);
+        let mut state = started(&config, &clock);
+
+        clock.advance(130.0);
+        let event = state.advance(&config, clock.now());
+        assert_eq!(
+            event,
+            TickEvent {
+                tick: 1,
+                interval_ms: 100,
+                scheduled_ms: 100.0,
+                actual_ms: 130.0,
+                drift_ms: 30.0,
+                corrected: false,
+                missed_ticks: 0,
+                remaining_ms: None,
+            }
+        );
+
+        // Eight more ticks, then the sync tick fires 300ms late and is corrected
+        for _ in 0..8 {
+            clock.advance(100.0);
+            state.advance(&config, clock.now());
+        }
+        clock.advance(370.0);
+        let event = state.advance(&config, clock.now());
+        assert_eq!(event.tick, 13);
+        assert_eq!(event.scheduled_ms, 1000.0);
+        assert_eq!(event.actual_ms, 1300.0);
+        assert_eq!(event.drift_ms, 300.0);
+        assert!(event.corrected);
+        assert_eq!(event.missed_ticks, 0);
+    }
+
+    #[test]
+    fn test_drift_is_only_checked_on_sync_interval() {
+        let config = TimerConfig::default();
+        let clock = ManualClock::new(0.0);
+        let mut state = started(&config, &clock);
+
+        clock.advance(1000.0);
+        assert_eq!(state.advance(&config, clock.now()).tick, 1);
+        assert_eq!(state.delay_until_next(clock.now()), 0);
+    }
+
+    #[test]
+    fn test_custom_threshold() {
+        let config = TimerConfig {
+            interval_ms: 100,
+            sync_interval_ticks: 5,
+            sync_threshold_ticks: 3,
+            ..TimerConfig::default()
+        };
+        let clock = ManualClock::new(0.0);
+        let mut state = started(&config, &clock);
+
+        // 3 ticks behind at the sync point stays within threshold
+        for _ in 0..4 {
+            clock.advance(100.0);
+            state.advance(&config, clock.now());
+        }
+        clock.advance(400.0);
+        assert_eq!(state.advance(&config, clock.now()).tick, 5);
+
+        // 4 ticks behind is corrected
+        for _ in 0..4 {
+            clock.advance(100.0);
+            state.advance(&config, clock.now());
+        }
+        clock.advance(200.0);
+        assert_eq!(state.advance(&config, clock.now()).tick, 14);
+    }
+
+    type TickLog = Rc<RefCell<Vec<(usize, f64)>>>;
+
+    fn recording_timer(
+        sim: &Simulation,
+    ) -> (Rc<CountdownTimer<impl Fn(TickEvent) + 'static>>, TickLog) {
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let timer = sim.timer(TimerConfig::default(), move |event| {
+            ticks_clone.borrow_mut().push((event.tick, event.actual_ms));
+        });
+        (timer, ticks)
+    }
+
+    #[test]
+    fn test_pause_resume_keeps_tick_count() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(350.0);
+        timer.pause();
+        assert!(timer.is_paused());
+        assert!(!timer.is_running());
+        assert_eq!(timer.elapsed_ticks(), 3);
+
+        sim.advance(5000.0);
+        assert_eq!(ticks.borrow().len(), 3);
+
+        timer.resume();
+        assert!(!timer.is_paused());
+        sim.advance(1000.0);
+
+        // The 50ms left of tick 4 carry over, then ticks continue from 4
+        let ticks = ticks.borrow();
+        assert_eq!(ticks[3], (4, 5400.0));
+        assert_eq!(ticks.last(), Some(&(13, 6300.0)));
+        for pair in ticks.windows(2) {
+            assert_eq!(pair[1].0, pair[0].0 + 1);
+        }
+    }
+
+    #[test]
+    fn test_pause_time_is_not_corrected_as_drift() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(900.0);
+        timer.pause();
+        sim.advance(60_000.0);
+        timer.resume();
+        // Tick 10 is a sync tick; a long pause must not jump the count
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().last(), Some(&(10, 61_000.0)));
+    }
+
+    #[test]
+    fn test_resume_without_pause_does_nothing() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.resume();
+        sim.advance(1000.0);
+        assert!(ticks.borrow().is_empty());
+
+        timer.start();
+        sim.advance(200.0);
+        timer.stop();
+        timer.resume();
+        sim.advance(1000.0);
+        assert_eq!(ticks.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_start_after_pause_restarts_count() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(300.0);
+        timer.pause();
+        timer.start();
+        assert!(!timer.is_paused());
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().last(), Some(&(1, 400.0)));
+    }
+
+    fn target_timer(
+        sim: &Simulation,
+        target: usize,
+    ) -> (
+        Rc<CountdownTimer<impl Fn(TickEvent) + 'static>>,
+        TickLog,
+        TickLog,
+    ) {
+        let (timer, ticks) = recording_timer(sim);
+        let completions = Rc::new(RefCell::new(Vec::new()));
+        let completions_clone = Rc::clone(&completions);
+        let timer_clone = Rc::downgrade(&timer);
+        let clock = sim.clock();
+        timer.set_target(target, move || {
+            let ticks = timer_clone.upgrade().map_or(0, |t| t.elapsed_ticks());
+            completions_clone.borrow_mut().push((ticks, clock.now()));
+        });
+        (timer, ticks, completions)
+    }
+
+    #[test]
+    fn test_target_completes_once_and_stops() {
+        let sim = Simulation::new();
+        let (timer, ticks, completions) = target_timer(&sim, 30);
+
+        assert_eq!(timer.remaining(), Some(30));
+        timer.start();
+        sim.advance(1500.0);
+        assert_eq!(timer.remaining(), Some(15));
+
+        sim.advance(10_000.0);
+        assert_eq!(*completions.borrow(), vec![(30, 3000.0)]);
+        assert_eq!(ticks.borrow().len(), 30);
+        assert_eq!(ticks.borrow().last(), Some(&(30, 3000.0)));
+        assert_eq!(timer.remaining(), Some(0));
+        assert!(!timer.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_target_completion_after_clock_jump_is_clamped() {
+        let sim = Simulation::new();
+        let (timer, ticks, completions) = target_timer(&sim, 30);
+
+        timer.start();
+        sim.advance(250.0);
+        sim.jump_clock(60_000.0);
+        sim.advance(0.0);
+
+        // Completion happens on the first tick after the jump, not at a sync tick
+        assert_eq!(*completions.borrow(), vec![(30, 60_250.0)]);
+        assert_eq!(ticks.borrow().last(), Some(&(30, 60_250.0)));
+    }
+
+    #[test]
+    fn test_target_not_completed_early() {
+        let sim = Simulation::new();
+        let (timer, _ticks, completions) = target_timer(&sim, 5);
+
+        timer.start();
+        sim.advance(400.0);
+        // The final timeout fires 40ms early
+        sim.delay_next(-40.0);
+        sim.advance(60.0);
+        assert!(completions.borrow().is_empty());
+        sim.advance(40.0);
+        assert_eq!(*completions.borrow(), vec![(5, 500.0)]);
+    }
+
+    #[test]
+    fn test_early_final_timeout_does_not_repeat_a_tick() {
+        for early_ms in [1.0, 0.4, 40.0] {
+            let sim = Simulation::new();
+            let (timer, ticks, completions) = target_timer(&sim, 5);
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let events_clone = Rc::clone(&events);
+            let _events = timer.subscribe(move |event| {
+                events_clone
+                    .borrow_mut()
+                    .push((event.tick, event.corrected));
+            });
+
+            timer.start();
+            sim.advance(400.0);
+            sim.delay_next(-early_ms);
+            sim.advance(200.0);
+            assert_eq!(
+                *events.borrow(),
+                (1..=5).map(|tick| (tick, false)).collect::<Vec<_>>(),
+                "final timeout {}ms early",
+                early_ms
+            );
+            // Re-armed for the rest of the interval, rounded up to a whole ms
+            let (tick, at_ms) = *ticks.borrow().last().unwrap();
+            assert_eq!(tick, 5);
+            assert!((500.0..501.0).contains(&at_ms), "ticked at {}", at_ms);
+            assert_eq!(*completions.borrow(), vec![(5, at_ms)]);
+        }
+    }
+
+    #[test]
+    fn test_target_late_timeouts_complete_within_one_interval() {
+        let sim = Simulation::new();
+        let (timer, _ticks, completions) = target_timer(&sim, 20);
+
+        timer.start();
+        // Every timeout fires 30ms late
+        while completions.borrow().is_empty() {
+            sim.delay_next(30.0);
+            sim.run_next();
+        }
+        let (ticks, at_ms) = completions.borrow()[0];
+        assert_eq!(ticks, 20);
+        assert!((2000.0..2100.0).contains(&at_ms), "completed at {}", at_ms);
+    }
+
+    #[test]
+    fn test_target_restarts_after_reset() {
+        let sim = Simulation::new();
+        let (timer, _ticks, completions) = target_timer(&sim, 3);
+
+        timer.start();
+        sim.advance(1000.0);
+        timer.reset();
+        assert_eq!(timer.remaining(), Some(3));
+        timer.start();
+        sim.advance(1000.0);
+        assert_eq!(completions.borrow().len(), 2);
+
+        timer.clear_target();
+        assert_eq!(timer.remaining(), None);
+    }
+
+    #[test]
+    fn test_stats_on_time() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(2000.0);
+        let stats = timer.stats();
+        assert_eq!(stats.callbacks, 20);
+        assert_eq!(stats.corrections, 0);
+        assert_eq!(stats.max_drift_ms, 0.0);
+        assert_eq!(stats.jitter_histogram, [20, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_stats_record_jitter_and_corrections() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.delay_next(7.0);
+        sim.run_next();
+        sim.delay_next(-30.0);
+        sim.run_next();
+        sim.advance(680.0);
+        // Tick 9 fires 400ms late, so overdue tick 10 follows immediately
+        // and is corrected on sync
+        sim.delay_next(400.0);
+        sim.advance(1000.0);
+
+        let stats = timer.stats();
+        assert_eq!(stats.corrections, 1);
+        assert_eq!(stats.max_drift_ms, 400.0);
+        assert_eq!(stats.jitter_histogram[0], stats.callbacks - 4);
+        assert_eq!(stats.jitter_histogram[2], 1);
+        assert_eq!(stats.jitter_histogram[4], 1);
+        assert_eq!(stats.jitter_histogram[6], 2);
+
+        timer.start();
+        assert_eq!(timer.stats().corrections, 1);
+        timer.stop();
+        timer.start();
+        assert_eq!(timer.stats(), TimerStats::default());
+    }
+
+    type Ticks = Rc<RefCell<Vec<usize>>>;
+    type BoxedTimer = CountdownTimer<Box<dyn Fn(TickEvent)>>;
+
+    fn policy_timer(
+        sim: &Simulation,
+        catch_up: CatchUpPolicy,
+    ) -> (Rc<CountdownTimer<impl Fn(TickEvent) + 'static>>, Ticks) {
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let config = TimerConfig {
+            catch_up,
+            ..TimerConfig::default()
+        };
+        let timer = sim.timer(
+            config,
+            tick_count(move |t| ticks_clone.borrow_mut().push(t)),
+        );
+        (timer, ticks)
+    }
+
+    /// Run 8 ticks on time, then fall 500ms behind before tick 9
+    fn fall_behind(sim: &Simulation) {
+        sim.advance(800.0);
+        sim.delay_next(500.0);
+        sim.advance(600.0);
+    }
+
+    #[test]
+    fn test_catch_up_skip() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Skip);
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 14]);
+        assert_eq!(timer.missed_ticks(), 0);
+    }
+
+    #[test]
+    fn test_catch_up_burst() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), (1..=14).collect::<Vec<_>>());
+        assert_eq!(timer.missed_ticks(), 0);
+    }
+
+    #[test]
+    fn test_catch_up_coalesce() {
+        let sim = Simulation::new();
+        let missed = Rc::new(RefCell::new(Vec::new()));
+        let missed_clone = Rc::clone(&missed);
+        let slot: Rc<RefCell<Weak<BoxedTimer>>> = Rc::new(RefCell::new(Weak::new()));
+        let slot_clone = Rc::clone(&slot);
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            ..TimerConfig::default()
+        };
+        let timer = sim.timer(
+            config,
+            Box::new(move |event: TickEvent| {
+                let timer = slot_clone.borrow().upgrade().unwrap();
+                assert_eq!(event.missed_ticks, timer.missed_ticks());
+                missed_clone
+                    .borrow_mut()
+                    .push((event.tick, event.missed_ticks));
+            }) as Box<dyn Fn(TickEvent)>,
+        );
+        *slot.borrow_mut() = Rc::downgrade(&timer);
+
+        timer.start();
+        fall_behind(&sim);
+        let missed = missed.borrow();
+        assert_eq!(missed.len(), 10);
+        assert_eq!(missed[8], (9, 0));
+        assert_eq!(missed[9], (14, 4));
+    }
+
+    #[test]
+    fn test_burst_stops_at_target() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        timer.set_target(12, || {});
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), (1..=12).collect::<Vec<_>>());
+        assert!(!timer.is_running());
+    }
+
+    /// Wall clock following the simulation's clock plus an adjustable offset,
+    /// so it can be set without moving the scheduler's time
+    struct WallClock {
+        base: Rc<ManualClock>,
+        offset: Cell<f64>,
+    }
+
+    impl Clock for WallClock {
+        fn now(&self) -> f64 {
+            self.base.now() + self.offset.get()
+        }
+    }
+
+    #[test]
+    fn test_clock_set_backwards_keeps_ticking() {
+        let sim = Simulation::new();
+        let wall = Rc::new(WallClock {
+            base: sim.clock(),
+            offset: Cell::new(3_600_000.0),
+        });
+        let ticks: Ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let timer = CountdownTimer::with_backend(
+            TimerConfig::default(),
+            wall.clone(),
+            sim.scheduler(),
+            tick_count(move |t| ticks_clone.borrow_mut().push(t)),
+        );
+
+        timer.start();
+        sim.advance(550.0);
+        // The wall clock is set back an hour
+        wall.offset.set(0.0);
+        sim.advance(1000.0);
+
+        let ticks = ticks.borrow();
+        assert_eq!(*ticks, (1..=15).collect::<Vec<_>>());
+        let stats = timer.stats();
+        assert_eq!(stats.clock_jumps, 1);
+        assert_eq!(stats.corrections, 0);
+    }
+
+    #[test]
+    fn test_subscribers_receive_ticks_in_order() {
+        let sim = Simulation::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_clone = Rc::clone(&log);
+        let timer = sim.timer(TimerConfig::default(), move |event| {
+            log_clone.borrow_mut().push(("main", event.tick));
+        });
+
+        let log_clone = Rc::clone(&log);
+        let display =
+            timer.subscribe(move |event| log_clone.borrow_mut().push(("display", event.tick)));
+        let log_clone = Rc::clone(&log);
+        let audio =
+            timer.subscribe(move |event| log_clone.borrow_mut().push(("audio", event.tick)));
+
+        timer.start();
+        sim.advance(100.0);
+        assert_eq!(
+            *log.borrow(),
+            vec![("main", 1), ("display", 1), ("audio", 1)]
+        );
+
+        drop(display);
+        sim.advance(100.0);
+        assert_eq!(log.borrow()[3..], [("main", 2), ("audio", 2)]);
+
+        audio.unsubscribe();
+        sim.advance(100.0);
+        assert_eq!(log.borrow()[5..], [("main", 3)]);
+    }
+
+    #[test]
+    fn test_subscriber_can_unsubscribe_another_during_tick() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let count = Rc::new(Cell::new(0));
+
+        let victim_slot: Rc<RefCell<Option<Subscription>>> = Rc::new(RefCell::new(None));
+        let slot_clone = Rc::clone(&victim_slot);
+        let _killer = timer.subscribe(move |_| {
+            slot_clone.borrow_mut().take();
+        });
+        let count_clone = Rc::clone(&count);
+        *victim_slot.borrow_mut() =
+            Some(timer.subscribe(move |_| count_clone.set(count_clone.get() + 1)));
+
+        timer.start();
+        sim.advance(300.0);
+        // The snapshot still delivers the first tick, then the listener is gone
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn test_subscription_outliving_timer_is_harmless() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let subscription = timer.subscribe(|_| {});
+        drop(timer);
+        drop(subscription);
+    }
+
+    #[test]
+    fn test_burst_reaches_subscribers() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Burst,
+            ..TimerConfig::default()
+        };
+        let timer = sim.timer(config, |_| {});
+        let ticks: Ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let _subscription = timer.subscribe(move |event| ticks_clone.borrow_mut().push(event.tick));
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), (1..=14).collect::<Vec<_>>());
+    }
+
+    type Boundaries = Rc<RefCell<Vec<(usize, usize)>>>;
+
+    fn record_boundaries() -> (Boundaries, impl Fn(usize, TickEvent) + 'static) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log_clone = Rc::clone(&log);
+        (log, move |count, event: TickEvent| {
+            log_clone.borrow_mut().push((count, event.tick))
+        })
+    }
+
+    #[test]
+    fn test_second_and_minute_boundaries() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let (seconds, on_second) = record_boundaries();
+        let (minutes, on_minute) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+        let _minutes = timer.on_minute(on_minute);
+
+        timer.start();
+        sim.advance(125_000.0);
+        let seconds = seconds.borrow();
+        assert_eq!(seconds.len(), 125);
+        assert_eq!(seconds[0], (1, 10));
+        assert_eq!(seconds[59], (60, 600));
+        assert_eq!(*minutes.borrow(), vec![(1, 600), (2, 1200)]);
+    }
+
+    #[test]
+    fn test_every_ticks_with_second_interval() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            interval_ms: 1000,
+            sync_interval_ticks: 1,
+            ..TimerConfig::default()
+        };
+        let timer = sim.timer(config, |_| {});
+        let (log, listener) = record_boundaries();
+        let _every = timer.every_ticks(3, listener);
+        let (seconds, on_second) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+
+        timer.start();
+        sim.advance(10_000.0);
+        assert_eq!(*log.borrow(), vec![(1, 3), (2, 6), (3, 9)]);
+        assert_eq!(seconds.borrow().len(), 10);
+    }
+
+    #[test]
+    fn test_boundary_fires_once_across_correction() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let (seconds, on_second) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+
+        timer.start();
+        sim.advance(800.0);
+        // Falls 2.5s behind: tick 10 is corrected to 34
+        sim.delay_next(2500.0);
+        sim.advance(2600.0);
+        assert_eq!(seconds.borrow()[0], (3, 34));
+        assert_eq!(seconds.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_boundaries_restart_with_timer() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let (seconds, on_second) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+
+        timer.start();
+        sim.advance(2000.0);
+        timer.reset();
+        timer.start();
+        sim.advance(1000.0);
+        assert_eq!(*seconds.borrow(), vec![(1, 10), (2, 20), (1, 10)]);
+    }
+
+    #[test]
+    fn test_stream_yields_every_tick() {
+        use futures::{FutureExt, StreamExt};
+
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let mut ticks = timer.stream();
+
+        assert!(ticks.next().now_or_never().is_none());
+        timer.start();
+        sim.advance(300.0);
+
+        let collected = futures::executor::block_on(async {
+            let mut collected = Vec::new();
+            for _ in 0..3 {
+                collected.push(ticks.next().await.unwrap().tick);
+            }
+            collected
+        });
+        assert_eq!(collected, vec![1, 2, 3]);
+        assert!(ticks.next().now_or_never().is_none());
+
+        drop(timer);
+        assert_eq!(ticks.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    fn test_dropped_stream_unsubscribes() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let ticks = timer.stream();
+        assert_eq!(timer.subscribers.borrow().len(), 1);
+        drop(ticks);
+        assert!(timer.subscribers.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_dropping_last_rc_stops_timer() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let weak = Rc::downgrade(&timer);
+
+        timer.start();
+        sim.advance(300.0);
+        assert_eq!(sim.scheduler().pending(), 1);
+
+        // The pending tick must not keep the timer alive
+        drop(timer);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(sim.scheduler().pending(), 0);
+        sim.advance(1000.0);
+        assert_eq!(ticks.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_dropping_timer_cancels_deferred_start() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let weak = Rc::downgrade(&timer);
+
+        timer.start_at_next(1000);
+        drop(timer);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(sim.scheduler().pending(), 0);
+        sim.advance(2000.0);
+        assert!(ticks.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_run_guard_stops_timer_on_drop() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        let guard = timer.start_guarded();
+        sim.advance(200.0);
+        guard.pause();
+        assert!(!guard.is_running());
+        guard.resume();
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().len(), 3);
+
+        // Stops even though `timer` is still held
+        drop(guard);
+        assert!(!timer.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+        sim.advance(1000.0);
+        assert_eq!(ticks.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_display_frame_interpolates_between_ticks() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        let elapsed = || timer.display_frame().elapsed_ms;
+
+        assert_eq!(elapsed(), 0.0);
+        timer.start();
+        sim.advance(250.0);
+        assert_eq!(
+            timer.display_frame(),
+            DisplayFrame {
+                tick: 2,
+                elapsed_ms: 250.0,
+                progress: None
+            }
+        );
+
+        timer.pause();
+        sim.advance(1000.0);
+        assert_eq!(elapsed(), 250.0);
+        timer.resume();
+        sim.advance(30.0);
+        assert_eq!(elapsed(), 280.0);
+
+        // A late tick holds the display at the boundary instead of running ahead
+        sim.delay_next(100.0);
+        sim.advance(70.0);
+        assert_eq!(timer.elapsed_ticks(), 2);
+        assert_eq!(elapsed(), 300.0);
+    }
+
+    #[test]
+    fn test_display_frame_scales_speed_and_tracks_target() {
+        let sim = Simulation::new();
+        let config = TimerConfig::builder().speed(10.0).build().unwrap();
+        let timer = sim.timer(config, |_| {});
+        timer.set_target(10, || {});
+
+        timer.start();
+        sim.advance(45.0);
+        let frame = timer.display_frame();
+        assert_eq!(frame.tick, 4);
+        assert!((frame.elapsed_ms - 450.0).abs() < 1e-9);
+        assert!((frame.progress.unwrap() - 0.45).abs() < 1e-9);
+
+        sim.advance(100.0);
+        assert_eq!(
+            timer.display_frame(),
+            DisplayFrame {
+                tick: 10,
+                elapsed_ms: 1000.0,
+                progress: Some(1.0)
+            }
+        );
+    }
+
+    #[test]
+    fn test_animate_until_loop_or_timer_dropped() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        let record = |frames: &Rc<RefCell<Vec<f64>>>| {
+            let frames = Rc::clone(frames);
+            move |frame: DisplayFrame| frames.borrow_mut().push(frame.elapsed_ms)
+        };
+
+        timer.start();
+        let animation = timer.animate(record(&frames));
+        sim.advance(50.0);
+        assert_eq!(*frames.borrow(), vec![16.0, 32.0, 48.0]);
+
+        drop(animation);
+        assert_eq!(sim.scheduler().pending(), 1);
+        sim.advance(100.0);
+        assert_eq!(frames.borrow().len(), 3);
+
+        // The frame already requested finds the timer gone and stops the loop
+        let _animation = timer.animate(record(&frames));
+        drop(timer);
+        sim.advance(100.0);
+        assert_eq!(frames.borrow().len(), 3);
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_set_interval_keeps_elapsed_time() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(1250.0);
+        assert_eq!(timer.elapsed_ticks(), 12);
+
+        // The 250ms into the second carry over into the first whole second
+        timer.set_interval(1000).unwrap();
+        assert_eq!(timer.config().interval_ms, 1000);
+        assert_eq!(timer.elapsed_ticks(), 1);
+        sim.advance(2000.0);
+        assert_eq!(ticks.borrow()[12..], [(2, 2000.0), (3, 3000.0)]);
+
+        timer.set_interval(100).unwrap();
+        assert_eq!(timer.elapsed_ticks(), 32);
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().last(), Some(&(33, 3300.0)));
+        assert_eq!(timer.stats().corrections, 0);
+    }
+
+    #[test]
+    fn test_set_interval_while_paused() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start();
+        sim.advance(350.0);
+        timer.pause();
+        timer.set_interval(1000).unwrap();
+        assert!(timer.is_paused());
+        assert_eq!(timer.elapsed_ticks(), 0);
+
+        sim.advance(5000.0);
+        timer.resume();
+        sim.advance(2000.0);
+        assert_eq!(ticks.borrow()[3..], [(1, 6000.0), (2, 7000.0)]);
+    }
+
+    #[test]
+    fn test_set_interval_converts_target() {
+        let sim = Simulation::new();
+        let (timer, _ticks, completions) = target_timer(&sim, 50);
+
+        timer.start();
+        sim.advance(1250.0);
+        timer.set_interval(1000).unwrap();
+        assert_eq!(timer.remaining(), Some(4));
+
+        sim.advance(10_000.0);
+        assert_eq!(*completions.borrow(), vec![(5, 5000.0)]);
+    }
+
+    #[test]
+    fn test_set_interval_rejects_invalid_interval() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(500.0);
+
+        assert_eq!(timer.set_interval(0), Err(TimerConfigError::ZeroInterval));
+        assert_eq!(timer.config(), TimerConfig::default());
+        assert_eq!(timer.elapsed_ticks(), 5);
+    }
+
+    #[test]
+    fn test_boundaries_follow_set_interval() {
+        let sim = Simulation::new();
+        let timer = sim.timer(TimerConfig::default(), |_| {});
+        let (seconds, on_second) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+
+        timer.start();
+        sim.advance(2500.0);
+        timer.set_interval(1000).unwrap();
+        sim.advance(2500.0);
+        assert_eq!(
+            *seconds.borrow(),
+            vec![(1, 10), (2, 20), (3, 3), (4, 4), (5, 5)]
+        );
+    }
+
+    fn aligned_config(interval_ms: u32) -> TimerConfig {
+        TimerConfig {
+            interval_ms,
+            align_to_wall_clock: true,
+            ..TimerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_aligned_ticks_land_on_wall_clock_boundaries() {
+        let sim = Simulation::new();
+        sim.advance(1234.0);
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let timer = sim.timer(aligned_config(100), move |event| {
+            ticks_clone.borrow_mut().push((event.tick, event.actual_ms));
+        });
+
+        // Snapped to the nearest boundary, so the first tick comes early
+        timer.start();
+        sim.advance(200.0);
+        assert_eq!(*ticks.borrow(), vec![(1, 1300.0), (2, 1400.0)]);
+
+        // A resume realigns the carried-over remainder too
+        sim.advance(16.0);
+        timer.pause();
+        sim.advance(3270.0);
+        timer.resume();
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow()[2..], [(3, 4800.0)]);
+    }
+
+    #[test]
+    fn test_aligned_timers_tick_together() {
+        let sim = Simulation::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let make_timer = |name: &'static str| {
+            let log = Rc::clone(&log);
+            sim.timer(aligned_config(1000), move |event| {
+                log.borrow_mut().push((name, event.tick, event.actual_ms));
+            })
+        };
+        let a = make_timer("a");
+        let b = make_timer("b");
+
+        sim.advance(1234.0);
+        a.start();
+        sim.advance(555.0);
+        b.start();
+        sim.advance(1500.0);
+        assert_eq!(
+            *log.borrow(),
+            vec![("a", 1, 2000.0), ("b", 1, 3000.0), ("a", 2, 3000.0)]
+        );
+    }
+
+    #[test]
+    fn test_start_at_next_full_second() {
+        let sim = Simulation::new();
+        sim.advance(1234.0);
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start_at_next(1000);
+        sim.advance(700.0);
+        assert!(!timer.is_running());
+        assert!(ticks.borrow().is_empty());
+
+        sim.advance(300.0);
+        assert!(timer.is_running());
+        assert_eq!(*ticks.borrow(), vec![(1, 2100.0), (2, 2200.0)]);
+    }
+
+    #[test]
+    fn test_stop_cancels_pending_start() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+
+        timer.start_at_next(60_000);
+        sim.advance(30_000.0);
+        timer.stop();
+        sim.advance(60_000.0);
+        assert!(!timer.is_running());
+        assert!(ticks.borrow().is_empty());
+    }
+
+    type Remaining = Rc<RefCell<Vec<(usize, f64, Option<f64>)>>>;
+    type Completions = Rc<Cell<usize>>;
+
+    fn deadline_timer(
+        sim: &Simulation,
+        clock: Rc<dyn Clock>,
+        config: TimerConfig,
+    ) -> (
+        Rc<CountdownTimer<impl Fn(TickEvent) + 'static>>,
+        Remaining,
+        Completions,
+    ) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let events_clone = Rc::clone(&events);
+        let timer = CountdownTimer::with_backend(config, clock, sim.scheduler(), move |event| {
+            events_clone
+                .borrow_mut()
+                .push((event.tick, event.actual_ms, event.remaining_ms));
+        });
+        let completions = Rc::new(Cell::new(0));
+        let completions_clone = Rc::clone(&completions);
+        timer.set_deadline(3050.0, move || {
+            completions_clone.set(completions_clone.get() + 1)
+        });
+        (timer, events, completions)
+    }
+
+    #[test]
+    fn test_deadline_completes_between_ticks() {
+        let sim = Simulation::new();
+        sim.advance(1000.0);
+        let (timer, events, completions) =
+            deadline_timer(&sim, sim.clock(), TimerConfig::default());
+        assert_eq!(timer.remaining_ms(), Some(2050.0));
+
+        timer.start();
+        sim.advance(2049.0);
+        assert_eq!(completions.get(), 0);
+        // The deadline completes without a tick event of its own
+        sim.advance(5000.0);
+        let events = events.borrow();
+        assert_eq!(events[0], (1, 1100.0, Some(1950.0)));
+        assert_eq!(events[19], (20, 3000.0, Some(50.0)));
+        assert_eq!(events.len(), 20);
+        assert_eq!(completions.get(), 1);
+        assert_eq!(timer.elapsed_ticks(), 20);
+        assert!(!timer.is_running());
+        assert_eq!(timer.remaining_ms(), Some(0.0));
+        assert_eq!(timer.stats().corrections, 0);
+    }
+
+    #[test]
+    fn test_deadline_on_a_tick_completes_with_it() {
+        let sim = Simulation::new();
+        sim.advance(1050.0);
+        let (timer, events, completions) =
+            deadline_timer(&sim, sim.clock(), TimerConfig::default());
+
+        timer.start();
+        sim.advance(5000.0);
+        assert_eq!(events.borrow().last(), Some(&(20, 3050.0, Some(0.0))));
+        assert_eq!(events.borrow().len(), 20);
+        assert_eq!(completions.get(), 1);
+    }
+
+    /// Monotonic clock that stands still while the device sleeps, as
+    /// `performance.now()` does on some platforms, beside a wall clock that
+    /// keeps going
+    struct SleepingClock {
+        base: Rc<ManualClock>,
+        slept: Cell<f64>,
+    }
+
+    impl Clock for SleepingClock {
+        fn now(&self) -> f64 {
+            self.base.now()
+        }
+
+        fn epoch_ms(&self) -> f64 {
+            self.base.now() + self.slept.get()
+        }
+    }
+
+    #[test]
+    fn test_deadline_absorbs_sleep() {
+        let sim = Simulation::new();
+        let clock = Rc::new(SleepingClock {
+            base: sim.clock(),
+            slept: Cell::new(0.0),
+        });
+        let config = TimerConfig::seconds();
+        let (timer, events, completions) = deadline_timer(&sim, clock.clone(), config);
+
+        timer.start();
+        sim.advance(1500.0);
+        clock.slept.set(1000.0);
+        sim.advance(1000.0);
+        assert_eq!(
+            *events.borrow(),
+            vec![(1, 1000.0, Some(2050.0)), (2, 2000.0, Some(50.0))]
+        );
+        assert_eq!(completions.get(), 1);
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn test_deadline_timeout_early_by_skew_waits() {
+        let sim = Simulation::new();
+        let clock = Rc::new(SleepingClock {
+            base: sim.clock(),
+            slept: Cell::new(0.0),
+        });
+        let (timer, events, completions) =
+            deadline_timer(&sim, clock.clone(), TimerConfig::default());
+
+        timer.start();
+        sim.advance(3000.0);
+        assert_eq!(events.borrow().last(), Some(&(30, 3000.0, Some(50.0))));
+        // The wall clock falls 1ms behind while the timeout is pending
+        clock.slept.set(-1.0);
+        sim.advance(50.0);
+        assert_eq!(completions.get(), 0);
+        sim.advance(50.0);
+        assert_eq!(events.borrow().len(), 30);
+        assert_eq!(completions.get(), 1);
+    }
+
+    #[test]
+    fn test_set_deadline_while_running_reschedules() {
+        let sim = Simulation::new();
+        let (timer, events, completions) =
+            deadline_timer(&sim, sim.clock(), TimerConfig::seconds());
+        timer.start();
+        sim.advance(1200.0);
+
+        let completed_at = Rc::new(Cell::new(None));
+        let completed_at_clone = Rc::clone(&completed_at);
+        let clock = sim.clock();
+        timer.set_deadline(1500.0, move || completed_at_clone.set(Some(clock.now())));
+        sim.advance(1000.0);
+        assert_eq!(completed_at.get(), Some(1500.0));
+        assert_eq!(events.borrow().last(), Some(&(1, 1000.0, Some(2050.0))));
+        // Replaced by the new deadline's callback
+        assert_eq!(completions.get(), 0);
+
+        timer.clear_target();
+        assert_eq!(timer.remaining_ms(), None);
+    }
+
+    #[test]
+    fn test_snapshot_restore_counts_time_away() {
+        let sim = Simulation::new();
+        let (timer, _ticks, _completions) = target_timer(&sim, 600);
+        timer.start();
+        sim.advance(1250.0);
+        let snapshot = timer.snapshot();
+        assert_eq!(snapshot.ticks_elapsed, 12);
+        assert_eq!(snapshot.start_epoch_ms, 0.0);
+        assert_eq!(snapshot.taken_at_epoch_ms, 1250.0);
+        drop(timer);
+
+        // The page reloads 5s later with a fresh timer
+        sim.advance(5000.0);
+        let (timer, ticks, completions) = target_timer(&sim, 0);
+        assert_eq!(timer.restore(&snapshot), Ok(50));
+        assert!(timer.is_running());
+        assert_eq!(timer.elapsed_ticks(), 62);
+        assert_eq!(timer.remaining(), Some(538));
+
+        sim.advance(100.0);
+        assert_eq!(*ticks.borrow(), vec![(63, 6300.0)]);
+        sim.advance(60_000.0);
+        assert_eq!(*completions.borrow(), vec![(600, 60_000.0)]);
+    }
+
+    #[test]
+    fn test_restore_paused_snapshot() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(350.0);
+        timer.pause();
+        let snapshot = timer.snapshot();
+
+        sim.advance(10_000.0);
+        let (restored, ticks) = recording_timer(&sim);
+        assert_eq!(restored.restore(&snapshot), Ok(0));
+        assert!(restored.is_paused());
+        assert_eq!(restored.elapsed_ticks(), 3);
+
+        restored.resume();
+        sim.advance(150.0);
+        assert_eq!(*ticks.borrow(), vec![(4, 10_400.0), (5, 10_500.0)]);
+    }
+
+    #[test]
+    fn test_restore_past_target_completes() {
+        let sim = Simulation::new();
+        let (timer, _ticks, _completions) = target_timer(&sim, 20);
+        timer.start();
+        sim.advance(1000.0);
+        let snapshot = timer.snapshot();
+
+        sim.advance(5000.0);
+        let (restored, _ticks, completions) = target_timer(&sim, 0);
+        assert_eq!(restored.restore(&snapshot), Ok(10));
+        sim.advance(0.0);
+        assert_eq!(*completions.borrow(), vec![(20, 6000.0)]);
+        assert!(!restored.is_running());
+    }
+
+    #[test]
+    fn test_restore_with_target_below_snapshot_ticks() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(5000.0);
+        timer.set_target(30, || {});
+        let snapshot = timer.snapshot();
+        assert_eq!(snapshot.ticks_elapsed, 50);
+
+        let (restored, _ticks, completions) = target_timer(&sim, 0);
+        assert_eq!(restored.restore(&snapshot), Ok(0));
+        assert_eq!(restored.elapsed_ticks(), 30);
+        sim.advance(100.0);
+        assert_eq!(completions.borrow().len(), 1);
+        assert!(!restored.is_running());
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_config() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(250.0);
+        let mut snapshot = timer.snapshot();
+        snapshot.config.interval_ms = 0;
+
+        assert_eq!(
+            timer.restore(&snapshot),
+            Err(TimerConfigError::ZeroInterval)
+        );
+        assert_eq!(timer.config().interval_ms, 100);
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().last(), Some(&(3, 300.0)));
+    }
+
+    #[test]
+    fn test_restore_rejects_non_finite_times() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(250.0);
+        let snapshot = timer.snapshot();
+
+        for edit in [
+            |s: &mut TimerSnapshot| s.start_epoch_ms = f64::NAN,
+            |s: &mut TimerSnapshot| s.paused_remaining_ms = Some(f64::INFINITY),
+            |s: &mut TimerSnapshot| s.deadline_ms = Some(f64::NEG_INFINITY),
+            |s: &mut TimerSnapshot| s.taken_at_epoch_ms = f64::NAN,
+        ] {
+            let mut edited = snapshot;
+            edit(&mut edited);
+            assert_eq!(
+                timer.restore(&edited),
+                Err(TimerConfigError::NonFiniteSnapshotTime)
+            );
+        }
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow().last(), Some(&(3, 300.0)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_json_round_trip() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        timer.start();
+        sim.advance(1250.0);
+        let snapshot = timer.snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let parsed: TimerSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[test]
+    fn test_speed_compresses_time() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            speed: 10.0,
+            ..TimerConfig::default()
+        };
+        let (timer, ticks, completions) = {
+            let ticks = Rc::new(RefCell::new(Vec::new()));
+            let ticks_clone = Rc::clone(&ticks);
+            let timer = sim.timer(config, move |event| {
+                ticks_clone.borrow_mut().push((event.tick, event.actual_ms));
+            });
+            let completions = Rc::new(Cell::new(0));
+            let completions_clone = Rc::clone(&completions);
+            timer.set_target(600, move || {
+                completions_clone.set(completions_clone.get() + 1)
+            });
+            (timer, ticks, completions)
+        };
+        let (seconds, on_second) = record_boundaries();
+        let _seconds = timer.on_second(on_second);
+
+        timer.start();
+        sim.advance(990.0);
+        assert_eq!(timer.elapsed_ticks(), 99);
+        assert_eq!(ticks.borrow()[0], (1, 10.0));
+        assert_eq!(seconds.borrow().len(), 9);
+
+        // A sync tick firing five ticks late is corrected as at 1.0
+        sim.delay_next(50.0);
+        sim.advance(110.0);
+        assert_eq!(timer.elapsed_ticks(), 110);
+        assert_eq!(timer.stats().corrections, 1);
+
+        sim.advance(10_000.0);
+        assert_eq!(completions.get(), 1);
+        assert_eq!(ticks.borrow().last(), Some(&(600, 6000.0)));
+        assert_eq!(seconds.borrow().len(), 60);
+    }
+
+    #[test]
+    fn test_speed_is_validated() {
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY, 200.0] {
+            assert!(matches!(
+                TimerConfig::builder().speed(speed).build(),
+                Err(TimerConfigError::InvalidSpeed(_))
+            ));
+        }
+        let config = TimerConfig::builder().speed(20.0).build().unwrap();
+        assert_eq!(config.tick_ms(), 5.0);
+    }
+
+    /// Run 3 ticks, then hide the tab: the next timeout is throttled to 5s
+    /// late and the page becomes visible again 2s later
+    fn hide_tab(sim: &Simulation) {
+        sim.advance(350.0);
+        sim.delay_next(5000.0);
+        sim.advance(2000.0);
+    }
+
+    #[test]
+    fn test_resync_catches_up_immediately() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        timer.start();
+        hide_tab(&sim);
+        assert_eq!(timer.elapsed_ticks(), 3);
+
+        let resync = timer.resync().unwrap();
+        assert_eq!(
+            resync,
+            Resync {
+                gap_ms: 2050.0,
+                missed_ticks: 20,
+            }
+        );
+        assert_eq!(timer.elapsed_ticks(), 23);
+        assert_eq!(timer.stats().corrections, 1);
+
+        // The throttled timeout was replaced by one on the resynced timeline
+        sim.advance(100.0);
+        assert_eq!(ticks.borrow()[3..], [(23, 2350.0), (24, 2400.0)]);
+        assert_eq!(sim.scheduler().pending(), 1);
+    }
+
+    #[test]
+    fn test_resync_follows_catch_up_policy() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        timer.start();
+        hide_tab(&sim);
+        timer.resync();
+        assert_eq!(*ticks.borrow(), (1..=23).collect::<Vec<_>>());
+
+        let sim = Simulation::new();
+        let missed = Rc::new(Cell::new(0));
+        let missed_clone = Rc::clone(&missed);
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            ..TimerConfig::default()
+        };
+        let timer = sim.timer(config, move |event| missed_clone.set(event.missed_ticks));
+        timer.start();
+        hide_tab(&sim);
+        timer.resync();
+        // The callback itself counts one of the 20 ticks jumped
+        assert_eq!(missed.get(), 19);
+    }
+
+    #[test]
+    fn test_coalesced_resync_counts_every_tick_once() {
+        let sim = Simulation::new();
+        let counted = Rc::new(Cell::new(0));
+        let counted_clone = Rc::clone(&counted);
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            ..TimerConfig::default()
+        };
+        // Counts ticks the way the EMOM app does
+        let timer = sim.timer(config, move |event| {
+            counted_clone.set(counted_clone.get() + event.missed_ticks + 1);
+        });
+
+        timer.start();
+        hide_tab(&sim);
+        assert_eq!(timer.resync().map(|resync| resync.missed_ticks), Some(20));
+        assert_eq!(timer.elapsed_ticks(), 23);
+        assert_eq!(counted.get(), 23);
+
+        sim.advance(1000.0);
+        assert_eq!(counted.get(), timer.elapsed_ticks());
+    }
+
+    #[test]
+    fn test_resync_when_on_time_does_nothing() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        assert_eq!(timer.resync(), None);
+
+        timer.start();
+        sim.advance(350.0);
+        assert_eq!(timer.resync().map(|resync| resync.missed_ticks), Some(0));
+        assert_eq!(ticks.borrow().len(), 3);
+        sim.advance(50.0);
+        assert_eq!(ticks.borrow().last(), Some(&(4, 400.0)));
+    }
+
+    #[test]
+    fn test_resync_completes_target() {
+        let sim = Simulation::new();
+        let (timer, ticks, completions) = target_timer(&sim, 10);
+        timer.start();
+        hide_tab(&sim);
+
+        assert_eq!(timer.resync().map(|resync| resync.missed_ticks), Some(7));
+        assert_eq!(ticks.borrow().last(), Some(&(10, 2350.0)));
+        assert_eq!(*completions.borrow(), vec![(10, 2350.0)]);
+        assert!(!timer.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_resync_completes_deadline_without_a_tick() {
+        let sim = Simulation::new();
+        let (timer, events, completions) =
+            deadline_timer(&sim, sim.clock(), TimerConfig::default());
+        timer.start();
+        sim.advance(3000.0);
+        sim.delay_next(5000.0);
+        sim.advance(55.0);
+
+        assert_eq!(timer.resync().map(|resync| resync.missed_ticks), Some(0));
+        assert_eq!(events.borrow().len(), 30);
+        assert_eq!(completions.get(), 1);
+        assert!(!timer.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_resync_when_visible_without_browser() {
+        let sim = Simulation::new();
+        let (timer, _ticks) = recording_timer(&sim);
+        assert!(timer.resync_when_visible(|_| {}).is_none());
+    }
+
+    /// Subscribe `command` to run against the timer on tick `at`
+    fn on_tick_at<F>(
+        timer: &Rc<CountdownTimer<F>>,
+        at: usize,
+        command: impl Fn(&Rc<CountdownTimer<F>>) + 'static,
+    ) -> Subscription
+    where
+        F: Fn(TickEvent) + 'static,
+    {
+        let weak = Rc::downgrade(timer);
+        let fired = Cell::new(false);
+        timer.subscribe(move |event| {
+            if event.tick == at
+                && !fired.replace(true)
+                && let Some(timer) = weak.upgrade()
+            {
+                command(&timer);
+            }
+        })
+    }
+
+    #[test]
+    fn test_stop_inside_tick() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let _stop = on_tick_at(&timer, 5, |timer| timer.stop());
+        let later = Rc::new(RefCell::new(Vec::new()));
+        let later_clone = Rc::clone(&later);
+        let _later = timer.subscribe(move |event| later_clone.borrow_mut().push(event.tick));
+
+        timer.start();
+        sim.advance(1000.0);
+        assert!(!timer.is_running());
+        assert_eq!(ticks.borrow().len(), 5);
+        // Listeners after the one that stopped still see the tick
+        assert_eq!(*later.borrow(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_stop_inside_burst_abandons_replay() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        let _stop = on_tick_at(&timer, 10, |timer| timer.stop());
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), (1..=10).collect::<Vec<_>>());
+        assert!(!timer.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_set_interval_inside_burst_abandons_replay() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        let _slow_down = on_tick_at(&timer, 10, |timer| timer.set_interval(1000).unwrap());
+
+        timer.start();
+        fall_behind(&sim);
+        // The 1400ms so far carry over as one whole second
+        assert_eq!(*ticks.borrow(), (1..=10).collect::<Vec<_>>());
+        assert_eq!(timer.elapsed_ticks(), 1);
+        assert_eq!(sim.scheduler().pending(), 1);
+
+        sim.advance(600.0);
+        assert_eq!(ticks.borrow()[10..], [2]);
+    }
+
+    #[test]
+    fn test_resync_inside_burst_abandons_replay() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        let clock = sim.clock();
+        let _resync = on_tick_at(&timer, 11, move |timer| {
+            // The callback itself runs long enough to miss more ticks
+            clock.advance(300.0);
+            timer.resync();
+        });
+
+        timer.start();
+        fall_behind(&sim);
+        let mut expected: Vec<_> = (1..=11).collect();
+        expected.extend([15, 16, 17]);
+        assert_eq!(*ticks.borrow(), expected);
+        assert_eq!(timer.elapsed_ticks(), 17);
+        assert_eq!(sim.scheduler().pending(), 1);
+    }
+
+    #[test]
+    fn test_set_deadline_inside_burst_abandons_replay() {
+        let sim = Simulation::new();
+        let (timer, ticks) = policy_timer(&sim, CatchUpPolicy::Burst);
+        let completions = Rc::new(Cell::new(0));
+        let completions_clone = Rc::clone(&completions);
+        let clock = sim.clock();
+        let _deadline = on_tick_at(&timer, 10, move |timer| {
+            let completions = Rc::clone(&completions_clone);
+            timer.set_deadline(clock.epoch_ms() + 250.0, move || {
+                completions.set(completions.get() + 1);
+            });
+        });
+
+        timer.start();
+        fall_behind(&sim);
+        assert_eq!(*ticks.borrow(), (1..=10).collect::<Vec<_>>());
+        assert_eq!(sim.scheduler().pending(), 1);
+
+        // Ticks continue on the corrected timeline until the new deadline
+        sim.advance(1000.0);
+        assert_eq!(ticks.borrow()[10..], [15, 16]);
+        assert_eq!(completions.get(), 1);
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn test_restart_inside_tick() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let _restart = on_tick_at(&timer, 3, |timer| {
+            timer.reset();
+            timer.start();
+        });
+
+        timer.start();
+        sim.advance(550.0);
+        assert_eq!(sim.scheduler().pending(), 1);
+        assert_eq!(
+            *ticks.borrow(),
+            vec![(1, 100.0), (2, 200.0), (3, 300.0), (1, 400.0), (2, 500.0)]
+        );
+    }
+
+    #[test]
+    fn test_pause_and_resume_inside_tick() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let _toggle = on_tick_at(&timer, 2, |timer| {
+            timer.pause();
+            timer.resume();
+        });
+
+        timer.start();
+        sim.advance(400.0);
+        assert_eq!(ticks.borrow().len(), 4);
+        assert_eq!(sim.scheduler().pending(), 1);
+    }
+
+    #[test]
+    fn test_restart_from_on_complete() {
+        let sim = Simulation::new();
+        let (timer, ticks) = recording_timer(&sim);
+        let weak = Rc::downgrade(&timer);
+        let rounds = Rc::new(Cell::new(0));
+        let rounds_clone = Rc::clone(&rounds);
+        timer.set_target(5, move || {
+            rounds_clone.set(rounds_clone.get() + 1);
+            if rounds_clone.get() < 3
+                && let Some(timer) = weak.upgrade()
+            {
+                timer.start();
+            }
+        });
+
+        timer.start();
+        sim.advance(2000.0);
+        assert_eq!(rounds.get(), 3);
+        assert_eq!(ticks.borrow().len(), 15);
+        assert_eq!(ticks.borrow().last(), Some(&(5, 1500.0)));
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn test_reset_on_final_tick_skips_completion() {
+        let sim = Simulation::new();
+        let (timer, _ticks, completions) = target_timer(&sim, 5);
+        let _reset = on_tick_at(&timer, 5, |timer| timer.reset());
+
+        timer.start();
+        sim.advance(1000.0);
+        assert!(completions.borrow().is_empty());
+        assert_eq!(timer.elapsed_ticks(), 0);
+    }
+}
@@ -1,4 +1,8 @@
+pub mod clock;
 pub mod countdown_timer;
+pub mod scheduler;
+pub mod simulation;
+pub mod timer_group;
 pub mod emomtimer {
     pub enum Msg {
         Start,
@@ -10,12 +14,37 @@ pub mod emomtimer {
         DecrementQuarter,
         IncrementRound,
         DecrementRound,
-        Tick,
+        CycleSpeed,
+        /// Count down this many tenths at once
+        Tick(usize),
     }
 
     pub const DEFAULT_MINUTES: usize = 1;
     pub const DEFAULT_SECONDS: usize = 0;
     pub const DEFAULT_ROUNDS: usize = 5;
+    pub const BLINKED_COUNT: usize = 3;
+    /// Preview speeds cycled through by [`Msg::CycleSpeed`], real time first
+    pub const PREVIEW_SPEEDS: [f64; 3] = [1.0, 10.0, 20.0];
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum BlinkState {
+        Red,
+        Green,
+        None,
+    }
+
+    /// What a single [`Timer::tick`] did to the workout
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum TickOutcome {
+        /// The timer was not running, nothing changed
+        Idle,
+        /// Still counting down within the current round
+        Counting,
+        /// A round ended and the next one started
+        RoundEnded,
+        /// The last round ended and the timer stopped
+        Finished,
+    }
 
     #[derive(Debug, Clone, Copy, PartialEq)]
     pub struct Time {
@@ -108,6 +137,48 @@ pub mod emomtimer {
         pub fn total_seconds(&self) -> usize {
             self.seconds + self.minutes * 60
         }
+
+        /// Seconds to wrap to when counting down a round of this length
+        pub fn max_seconds(&self) -> usize {
+            if self.minutes > 0 {
+                60
+            } else {
+                self.seconds.max(1)
+            }
+        }
+
+        /// Display value one tick into a round of this length when starting
+        /// This ensures we count from 59.9 -> 0.0 for a 60 second round
+        pub fn first_tick(&self) -> Time {
+            let mut time = *self;
+            if time.tenths > 0 {
+                time.tenths -= 1;
+            } else if time.seconds > 0 {
+                time.seconds -= 1;
+                time.tenths = 9;
+            } else if time.minutes > 0 {
+                time.minutes -= 1;
+                time.seconds = 59;
+                time.tenths = 9;
+            }
+            time
+        }
+
+        /// Display value one tick into the next round, borrowing through
+        /// `decrement_seconds`; unlike `first_tick` a 1 second round reaches 0.0
+        pub fn next_round_tick(&self) -> Time {
+            let mut time = *self;
+            if time.tenths > 0 {
+                time.tenths -= 1;
+            } else {
+                // If tenths is 0, we need to borrow from seconds
+                time.decrement_seconds(self.max_seconds());
+                if !time.is_zero() {
+                    time.tenths = 9;
+                }
+            }
+            time
+        }
     }
 
     pub struct Timer {
@@ -137,6 +208,83 @@ pub mod emomtimer {
                 self.current_round = self.rounds;
             }
         }
+
+        /// Start or resume counting rounds of `round_time`
+        /// Returns false if the timer was already running
+        pub fn start(&mut self, round_time: &Time) -> bool {
+            if self.running {
+                return false;
+            }
+
+            let is_resume = self.current_time != *round_time;
+            if !is_resume {
+                if self.current_round >= self.rounds {
+                    self.current_round = 1;
+                }
+                self.current_time = round_time.first_tick();
+            }
+
+            self.running = true;
+            true
+        }
+
+        /// Count down one tenth, rolling over to the next round at zero
+        pub fn tick(&mut self, round_time: &Time) -> TickOutcome {
+            if !self.running {
+                return TickOutcome::Idle;
+            }
+
+            self.current_time.tick(round_time.max_seconds());
+            if !self.current_time.is_zero() {
+                return TickOutcome::Counting;
+            }
+
+            if self.current_round >= self.rounds {
+                // Last round - reset to full configured time
+                self.current_time = *round_time;
+                self.running = false;
+                TickOutcome::Finished
+            } else {
+                // Not the last round - start next round at one tick before configured time
+                self.current_time = round_time.next_round_tick();
+                self.current_round += 1;
+                TickOutcome::RoundEnded
+            }
+        }
+
+        pub fn blink_state(&self, round_time: &Time) -> BlinkState {
+            let total_seconds = self.current_time.total_seconds();
+            let round_seconds = round_time.total_seconds();
+            let tenths = self.current_time.tenths;
+
+            // Only blink if round is long enough
+            // Otherwise the blinking is too constant and distracting
+            // this actually blinks 3 times starting
+            // 3 times ending - so you need at least 7 seconds to
+            // see all the blinking
+            if round_seconds <= 2 * BLINKED_COUNT + 1 {
+                return BlinkState::None;
+            }
+
+            // Blink green at 1, 2, and 3 second marks after round starts
+            // Only after round 1 has started
+            // E.g., for 60 seconds: blink at 59, 58, 57 (when 1, 2, 3 seconds have elapsed)
+            // Blink for 0.5 seconds (tenths 0-4) at the start of each second
+            if self.current_round > 1
+                && total_seconds > round_seconds - (BLINKED_COUNT + 1)
+                && total_seconds < round_seconds
+                && tenths <= 4
+            {
+                BlinkState::Green
+            }
+            // Blink red at the end of the round (last 3 seconds)
+            // Blink for 0.5 seconds (tenths 0-4) at seconds 3, 2, 1
+            else if total_seconds > 0 && total_seconds <= BLINKED_COUNT && tenths <= 4 {
+                BlinkState::Red
+            } else {
+                BlinkState::None
+            }
+        }
     }
 
     pub fn distance<T>(a: T, b: T) -> T
@@ -512,6 +660,82 @@ pub mod emomtimer {
             assert_eq!(history[1], (0, 14, 9));
         }
 
+        #[test]
+        fn test_first_tick() {
+            let minute = Time {
+                seconds: 0,
+                minutes: 1,
+                tenths: 0,
+            };
+            assert_eq!(
+                minute.first_tick(),
+                Time {
+                    seconds: 59,
+                    minutes: 0,
+                    tenths: 9
+                }
+            );
+            let zero = Time {
+                seconds: 0,
+                minutes: 0,
+                tenths: 0,
+            };
+            assert_eq!(zero.first_tick(), zero);
+
+            // Starting a 1 second round shows 0.9 rather than finishing at once
+            let second = Time {
+                seconds: 1,
+                minutes: 0,
+                tenths: 0,
+            };
+            assert_eq!(
+                second.first_tick(),
+                Time {
+                    seconds: 0,
+                    minutes: 0,
+                    tenths: 9
+                }
+            );
+            assert_eq!(second.next_round_tick(), zero);
+            assert_eq!(minute.next_round_tick(), minute.first_tick());
+        }
+
+        #[test]
+        fn test_timer_tick_outcomes() {
+            let round_time = Time {
+                seconds: 2,
+                minutes: 0,
+                tenths: 0,
+            };
+            let mut timer = Timer {
+                current_time: round_time,
+                rounds: 2,
+                current_round: 1,
+                running: false,
+            };
+            assert_eq!(timer.tick(&round_time), TickOutcome::Idle);
+            assert!(timer.start(&round_time));
+            assert!(!timer.start(&round_time));
+            assert_eq!(timer.current_time.seconds, 1);
+            assert_eq!(timer.current_time.tenths, 9);
+
+            // 1.9 -> 1.0
+            for _ in 0..9 {
+                assert_eq!(timer.tick(&round_time), TickOutcome::Counting);
+            }
+            assert_eq!(timer.tick(&round_time), TickOutcome::RoundEnded);
+            assert_eq!(timer.current_round, 2);
+            assert_eq!(timer.current_time.seconds, 1);
+            assert_eq!(timer.current_time.tenths, 9);
+
+            for _ in 0..9 {
+                timer.tick(&round_time);
+            }
+            assert_eq!(timer.tick(&round_time), TickOutcome::Finished);
+            assert_eq!(timer.current_time, round_time);
+            assert!(!timer.running);
+        }
+
         #[test]
         fn test_total_seconds() {
             let mut time = Time {
@@ -1,139 +1,134 @@
-use emom::countdown_timer::{CountdownTimer, TimerConfig};
-use log::{debug, info};
+use emom::clock::PerformanceClock;
+use emom::countdown_timer::{
+    CatchUpPolicy, CountdownTimer, RunGuard, TickEvent, TimerConfig, VisibilityListener,
+};
+use emom::scheduler::{BrowserScheduler, Scheduler, WorkerScheduler};
+use log::{debug, info, warn};
 use std::rc::Rc;
 use yew::{Component, Context, Html, classes, html};
 
-use emom::emomtimer::{DEFAULT_MINUTES, DEFAULT_ROUNDS, DEFAULT_SECONDS, Msg, Time, Timer};
+use emom::emomtimer::{
+    BlinkState, DEFAULT_MINUTES, DEFAULT_ROUNDS, DEFAULT_SECONDS, Msg, PREVIEW_SPEEDS, TickOutcome,
+    Time, Timer,
+};
 
-const BLINKED_COUNT: usize = 3;
-
-// Type alias to simplify complex timer type
-type TimerCallback = Rc<CountdownTimer<Box<dyn Fn(usize) + 'static>>>;
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum BlinkState {
-    Red,
-    Green,
-    None,
-}
+// Type alias to simplify complex timer type; dropping the guard stops the timer
+type TimerGuard = RunGuard<Box<dyn Fn(TickEvent) + 'static>>;
 
 pub struct App {
     round_time: Time,
     timer: Timer,
     blink_state: BlinkState,
-    countdown_timer: Option<TimerCallback>,
+    countdown_timer: Option<TimerGuard>,
+    /// Real time multiplier, above 1.0 to preview a workout
+    speed: f64,
+    /// Resyncs the countdown when the tab becomes visible again
+    visibility: Option<VisibilityListener>,
 }
 
 impl App {
     fn start(&mut self, ctx: &Context<Self>) {
-        if self.timer.running {
+        if !self.timer.start(&self.round_time) {
             return;
         }
 
-        let is_resume = self.timer.current_time != self.round_time;
-        if !is_resume {
-            if self.timer.current_round >= self.timer.rounds {
-                self.timer.current_round = 1;
-            }
-
-            // Adjust display to one tick before configured time when starting
-            // This ensures we count from 59.9 -> 0.0 for a 60 second timer
-            self.timer.current_time = self.round_time;
-            if self.timer.current_time.tenths > 0 {
-                self.timer.current_time.tenths -= 1;
-            } else if self.timer.current_time.seconds > 0 {
-                self.timer.current_time.seconds -= 1;
-                self.timer.current_time.tenths = 9;
-            } else if self.timer.current_time.minutes > 0 {
-                self.timer.current_time.minutes -= 1;
-                self.timer.current_time.seconds = 59;
-                self.timer.current_time.tenths = 9;
-            }
+        // Continue a paused countdown so the tick phase is preserved
+        if let Some(timer) = &self.countdown_timer {
+            timer.resume();
+            return;
         }
 
-        self.timer.running = true;
-
         // Create countdown timer with callback
         let link = ctx.link().clone();
-        let config = TimerConfig::default();
-        let countdown_timer: TimerCallback = CountdownTimer::new(
+        // Missed ticks arrive folded into one callback and are fast-forwarded
+        // in a single update. Aligned ticks keep every screen in the gym
+        // flipping together.
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            align_to_wall_clock: true,
+            speed: self.speed,
+            ..TimerConfig::default()
+        };
+        // Timeouts run in a worker so the liquid-distortion filter repainting
+        // the main thread cannot delay ticks; plain setTimeout if blocked
+        let scheduler: Rc<dyn Scheduler> = match WorkerScheduler::new() {
+            Ok(worker) => Rc::new(worker),
+            Err(err) => {
+                warn!("web worker unavailable, using setTimeout: {:?}", err);
+                Rc::new(BrowserScheduler)
+            }
+        };
+        let countdown_timer = CountdownTimer::with_backend(
             config,
-            Box::new(move |_ticks| {
-                link.send_message(Msg::Tick);
-            }) as Box<dyn Fn(usize) + 'static>,
-        );
+            Rc::new(PerformanceClock),
+            scheduler,
+            Box::new(move |event: TickEvent| {
+                link.send_message(Msg::Tick(event.missed_ticks + 1));
+            }) as Box<dyn Fn(TickEvent) + 'static>,
+        )
+        .start_guarded();
 
-        countdown_timer.start();
+        self.visibility = countdown_timer.resync_when_visible(|resync| {
+            info!(
+                "visible after {:.0}ms, caught up {} ticks",
+                resync.gap_ms, resync.missed_ticks
+            );
+        });
         self.countdown_timer = Some(countdown_timer);
     }
 
-    fn tick(&mut self, _ctx: &Context<Self>) {
+    /// Count down `ticks` tenths, more than one when fast-forwarding through
+    /// ticks missed while the tab was hidden or throttled
+    fn tick(&mut self, ticks: usize) {
         // Ignore ticks if timer is not running (prevents race conditions with old timeouts)
         if !self.timer.running {
             info!("Ignoring tick - timer not running");
             return;
         }
-
-        debug!(
-            "Tick: round {}/{}, time {}:{}.{}",
-            self.timer.current_round,
-            self.timer.rounds,
-            self.timer.current_time.minutes,
-            self.timer.current_time.seconds,
-            self.timer.current_time.tenths,
-        );
-
-        // Perform smooth tick countdown
-        self.timer.current_time.tick(self.max_seconds());
-
-        // Handle end of round
-        if self.timer.current_time.is_zero() {
-            self.tick_update_end_of_round();
-        } else {
-            self.update_blink_state();
+        if ticks > 1 {
+            info!("fast-forwarding {} ticks", ticks);
         }
-    }
 
-    fn tick_update_end_of_round(&mut self) {
-        info!("end of round");
+        for _ in 0..ticks {
+            debug!(
+                "Tick: round {}/{}, time {}:{}.{}",
+                self.timer.current_round,
+                self.timer.rounds,
+                self.timer.current_time.minutes,
+                self.timer.current_time.seconds,
+                self.timer.current_time.tenths,
+            );
 
-        if self.timer.current_round >= self.timer.rounds {
-            // Last round - reset to full configured time
-            info!("end of timer");
-            self.timer.current_time = self.round_time;
-            self.blink_state = BlinkState::None;
-            self.cancel();
-        } else {
-            // Not the last round - start next round at one tick before configured time
-            self.timer.current_time = self.round_time;
-            if self.timer.current_time.tenths > 0 {
-                self.timer.current_time.tenths -= 1;
-            } else {
-                // If tenths is 0, we need to borrow from seconds
-                self.timer
-                    .current_time
-                    .decrement_seconds(self.max_seconds());
-                if !self.timer.current_time.is_zero() {
-                    self.timer.current_time.tenths = 9;
+            // Perform smooth tick countdown
+            match self.timer.tick(&self.round_time) {
+                TickOutcome::Finished => {
+                    info!("end of round");
+                    info!("end of timer");
+                    self.blink_state = BlinkState::None;
+                    self.cancel();
+                    return;
+                }
+                TickOutcome::RoundEnded => {
+                    info!("end of round");
+                    self.blink_state = BlinkState::None;
                 }
+                TickOutcome::Counting | TickOutcome::Idle => self.update_blink_state(),
             }
-            self.timer.current_round += 1;
-            self.blink_state = BlinkState::None;
         }
     }
 
     fn cancel(&mut self) {
+        self.visibility = None;
         if let Some(timer) = self.countdown_timer.take() {
-            timer.stop();
+            info!("timer stats: {:?}", timer.stats());
         }
         self.timer.running = false;
         self.blink_state = BlinkState::None;
     }
 
     fn reset(&mut self) {
-        if let Some(timer) = self.countdown_timer.take() {
-            timer.stop();
-        }
+        self.visibility = None;
+        self.countdown_timer = None;
         self.round_time.reset();
         self.timer.reset();
         self.blink_state = BlinkState::None;
@@ -141,7 +136,27 @@ impl App {
 
     fn stop(&mut self) {
         info!("stopping");
-        self.cancel();
+        if let Some(timer) = &self.countdown_timer {
+            timer.pause();
+        }
+        self.timer.running = false;
+        self.blink_state = BlinkState::None;
+    }
+
+    /// Step to the next preview speed; only while not running, so the next
+    /// start builds a countdown at the new speed
+    fn cycle_speed(&mut self) {
+        if self.timer.running {
+            return;
+        }
+        let next = PREVIEW_SPEEDS
+            .iter()
+            .position(|&speed| speed == self.speed)
+            .map_or(0, |i| (i + 1) % PREVIEW_SPEEDS.len());
+        self.speed = PREVIEW_SPEEDS[next];
+        // A paused countdown was built for the old speed
+        self.visibility = None;
+        self.countdown_timer = None;
     }
 
     fn adjust_time_by_seconds(&mut self, delta_seconds: i64) {
@@ -168,51 +183,12 @@ impl App {
         self.timer.current_time.tenths = (new_current_tenths % 10) as usize;
     }
 
-    fn max_seconds(&self) -> usize {
-        if self.round_time.minutes > 0 {
-            60
-        } else {
-            self.round_time.seconds.max(1)
-        }
-    }
-
     fn clear_blink_state(&mut self) {
         self.blink_state = BlinkState::None;
     }
 
     fn update_blink_state(&mut self) {
-        let total_seconds = self.timer.current_time.total_seconds();
-        let round_seconds = self.round_time.total_seconds();
-        let tenths = self.timer.current_time.tenths;
-
-        // Only blink if round is long enough
-        // Otherwise the blinking is too constant and distracting
-        // this actually blinks 3 times starting
-        // 3 times ending - so you need at least 7 seconds to
-        // see all the blinking
-        if round_seconds <= 2 * BLINKED_COUNT + 1 {
-            self.blink_state = BlinkState::None;
-            return;
-        }
-
-        // Blink green at 1, 2, and 3 second marks after round starts
-        // Only after round 1 has started
-        // E.g., for 60 seconds: blink at 59, 58, 57 (when 1, 2, 3 seconds have elapsed)
-        // Blink for 0.5 seconds (tenths 0-4) at the start of each second
-        if self.timer.current_round > 1
-            && total_seconds > round_seconds - (BLINKED_COUNT + 1)
-            && total_seconds < round_seconds
-            && tenths <= 4
-        {
-            self.blink_state = BlinkState::Green;
-        }
-        // Blink red at the end of the round (last 3 seconds)
-        // Blink for 0.5 seconds (tenths 0-4) at seconds 3, 2, 1
-        else if total_seconds > 0 && total_seconds <= BLINKED_COUNT && tenths <= 4 {
-            self.blink_state = BlinkState::Red;
-        } else {
-            self.blink_state = BlinkState::None;
-        }
+        self.blink_state = self.timer.blink_state(&self.round_time);
     }
 }
 
@@ -237,23 +213,23 @@ impl Component for App {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: PREVIEW_SPEEDS[0],
+            visibility: None,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Start => {
-                if self.countdown_timer.is_none() {
-                    self.start(ctx);
-                }
+                self.start(ctx);
                 true
             }
             Msg::Stop => {
                 self.stop();
                 true
             }
-            Msg::Tick => {
-                self.tick(ctx);
+            Msg::Tick(ticks) => {
+                self.tick(ticks);
                 true
             }
             Msg::Reset => {
@@ -296,6 +272,11 @@ impl Component for App {
                 self.clear_blink_state();
                 true
             }
+            Msg::CycleSpeed => {
+                self.cycle_speed();
+                info!("preview speed {}x", self.speed);
+                true
+            }
         }
     }
 
@@ -310,6 +291,8 @@ impl Component for App {
         let on_subtract_second = ctx.link().callback(|_| Msg::DecrementSecond);
         let on_add_quarter = ctx.link().callback(|_| Msg::IncrementQuarter);
         let on_subtract_quarter = ctx.link().callback(|_| Msg::DecrementQuarter);
+        let on_cycle_speed = ctx.link().callback(|_| Msg::CycleSpeed);
+        let speed_label = format!("{}x", self.speed);
         let start_label = if !state.running && state.current_time != self.round_time {
             "Start ↻"
         } else {
@@ -363,6 +346,7 @@ impl Component for App {
                     <button aria-label="Decrement Second" onclick={ on_subtract_second } id="decrementSecondButton">{ "-1" }</button>
                     <button aria-label="Increment Second" onclick={ on_add_second } id="incrementSecondButton">{ "+1" }</button>
                     <button aria-label="Reset" onclick={ reset } id="resetButton">{ "Reset" }</button>
+                    <button aria-label="Preview Speed" onclick={ on_cycle_speed } id="speedButton">{ speed_label }</button>
                 </div>
                 </div>
             </body>
@@ -401,8 +385,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -425,8 +411,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 1);
+        assert_eq!(app.round_time.max_seconds(), 1);
     }
 
     #[test]
@@ -449,8 +437,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 1);
+        assert_eq!(app.round_time.max_seconds(), 1);
     }
 
     #[test]
@@ -473,6 +463,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.adjust_time_by_seconds(-15);
         assert_eq!(app.round_time.minutes, 0);
@@ -502,6 +494,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.adjust_time_by_seconds(15);
         assert_eq!(app.round_time.minutes, 1);
@@ -531,8 +525,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -555,8 +551,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -579,8 +577,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -603,8 +603,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -627,8 +629,10 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
-        assert_eq!(app.max_seconds(), 60);
+        assert_eq!(app.round_time.max_seconds(), 60);
     }
 
     #[test]
@@ -651,6 +655,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Red);
@@ -676,6 +682,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Red);
@@ -701,6 +709,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -726,6 +736,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Red);
@@ -751,6 +763,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -776,6 +790,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -801,6 +817,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Green);
@@ -826,6 +844,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Green);
@@ -851,6 +871,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -876,6 +898,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -901,6 +925,8 @@ mod tests {
             },
             blink_state: BlinkState::Red,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.clear_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -926,6 +952,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::None);
@@ -951,6 +979,8 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Green);
@@ -976,8 +1006,83 @@ mod tests {
             },
             blink_state: BlinkState::None,
             countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
         };
         app.update_blink_state();
         assert_eq!(app.blink_state, BlinkState::Red);
     }
+
+    #[test]
+    fn test_cycle_speed() {
+        let time = Time {
+            seconds: 0,
+            minutes: 1,
+            tenths: 0,
+        };
+        let mut app = App {
+            round_time: time,
+            timer: Timer {
+                current_time: time,
+                rounds: 20,
+                current_round: 1,
+                running: false,
+            },
+            blink_state: BlinkState::None,
+            countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
+        };
+        app.cycle_speed();
+        assert_eq!(app.speed, 10.0);
+        app.cycle_speed();
+        assert_eq!(app.speed, 20.0);
+        app.cycle_speed();
+        assert_eq!(app.speed, 1.0);
+
+        // Speed cannot change mid-workout
+        app.timer.running = true;
+        app.cycle_speed();
+        assert_eq!(app.speed, 1.0);
+    }
+
+    #[test]
+    fn test_tick_fast_forwards_rounds() {
+        let time = Time {
+            seconds: 10,
+            minutes: 0,
+            tenths: 0,
+        };
+        let mut app = App {
+            round_time: time,
+            timer: Timer {
+                current_time: time,
+                rounds: 3,
+                current_round: 1,
+                running: true,
+            },
+            blink_state: BlinkState::None,
+            countdown_timer: None,
+            speed: 1.0,
+            visibility: None,
+        };
+        // 10.5 seconds missed while hidden. Time::tick goes from 1.0 straight
+        // to the next round, so round 1 ends on tick 91 and round 2 starts at
+        // 9.9, leaving 8.5 after the remaining 14 ticks
+        app.tick(105);
+        assert_eq!(app.timer.current_round, 2);
+        assert_eq!(
+            app.timer.current_time,
+            Time {
+                seconds: 8,
+                minutes: 0,
+                tenths: 5,
+            }
+        );
+
+        // Ticks past the end stop at the finish
+        app.tick(1000);
+        assert!(!app.timer.running);
+        assert_eq!(app.timer.current_round, 3);
+    }
 }
@@ -0,0 +1,380 @@
+//! Deferred callback backends for the countdown timer
+//!
+//! The timer only ever needs "run this once after N milliseconds", so a
+//! backend is a single `schedule` call returning a cancellable handle.
+//! Display animation additionally asks for the next frame, which defaults to
+//! a timeout at roughly 60 frames per second.
+
+use gloo_events::EventListener;
+use gloo_render::request_animation_frame;
+use gloo_timers::callback::Timeout;
+use js_sys::Array;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, MessageEvent, Url, Worker};
+
+/// Delay standing in for an animation frame on backends without a display
+pub const FRAME_INTERVAL_MS: u32 = 16;
+
+pub trait Scheduler {
+    /// Run `callback` once after `delay_ms` milliseconds
+    fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle;
+
+    /// Run `callback` once before the next repaint
+    fn schedule_frame(&self, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        self.schedule(FRAME_INTERVAL_MS, callback)
+    }
+}
+
+/// A pending callback. Cancelling or dropping the handle prevents it from running.
+pub struct TaskHandle {
+    cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl TaskHandle {
+    pub fn new(cancel: impl FnOnce() + 'static) -> Self {
+        Self {
+            cancel: Some(Box::new(cancel)),
+        }
+    }
+
+    pub fn cancel(mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+/// Browser backend using `setTimeout` and `requestAnimationFrame` via gloo
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BrowserScheduler;
+
+impl Scheduler for BrowserScheduler {
+    fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        let timeout = Timeout::new(delay_ms, callback);
+        TaskHandle::new(move || {
+            timeout.cancel();
+        })
+    }
+
+    fn schedule_frame(&self, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        let frame = request_animation_frame(move |_timestamp| callback());
+        TaskHandle::new(move || drop(frame))
+    }
+}
+
+/// Worker side of [`WorkerScheduler`]: `[id, delay]` starts a timeout that
+/// posts `id` back when due, `[id, -1]` cancels it
+const WORKER_SCRIPT: &str = "\
+const timeouts = new Map();
+onmessage = (event) => {
+  const [id, delay] = event.data;
+  if (delay < 0) {
+    clearTimeout(timeouts.get(id));
+    timeouts.delete(id);
+    return;
+  }
+  timeouts.set(id, setTimeout(() => {
+    timeouts.delete(id);
+    postMessage(id);
+  }, delay));
+};
+";
+
+/// Callbacks waiting on timeouts in another context, e.g. a Web Worker
+///
+/// `post(id, delay_ms)` asks the other side to call [`fire`](Self::fire)
+/// with `id` after `delay_ms`; a negative delay cancels `id`. Keeping this
+/// apart from the worker glue lets the bookkeeping be tested natively.
+struct RemoteTasks {
+    post: Box<dyn Fn(u64, f64)>,
+    tasks: RefCell<HashMap<u64, Box<dyn FnOnce()>>>,
+    next_id: Cell<u64>,
+}
+
+impl RemoteTasks {
+    fn new(post: impl Fn(u64, f64) + 'static) -> Self {
+        Self {
+            post: Box::new(post),
+            tasks: RefCell::new(HashMap::new()),
+            next_id: Cell::new(0),
+        }
+    }
+
+    fn schedule(self: &Rc<Self>, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.tasks.borrow_mut().insert(id, callback);
+        (self.post)(id, f64::from(delay_ms));
+
+        let tasks = Rc::downgrade(self);
+        TaskHandle::new(move || {
+            if let Some(tasks) = tasks.upgrade()
+                && tasks.tasks.borrow_mut().remove(&id).is_some()
+            {
+                (tasks.post)(id, -1.0);
+            }
+        })
+    }
+
+    /// Run the callback for `id` unless it was cancelled or already ran
+    fn fire(&self, id: u64) {
+        let task = self.tasks.borrow_mut().remove(&id);
+        if let Some(task) = task {
+            task();
+        }
+    }
+}
+
+/// Browser backend running `setTimeout` inside a dedicated Web Worker
+///
+/// Worker timers are not held up by rendering on the main thread and are
+/// throttled far less in background tabs. The worker posts each due task
+/// back, and its callback runs on the main thread as usual. Frames still use
+/// `requestAnimationFrame`. Dropping the scheduler terminates the worker.
+pub struct WorkerScheduler {
+    tasks: Rc<RemoteTasks>,
+    worker: Worker,
+    script_url: String,
+    _listener: EventListener,
+}
+
+impl WorkerScheduler {
+    /// Start the worker. Fails where workers are unavailable or a content
+    /// security policy blocks `blob:` workers (allow them with
+    /// `worker-src blob:`); fall back to [`BrowserScheduler`] in that case.
+    pub fn new() -> Result<Self, JsValue> {
+        let parts = Array::of1(&JsValue::from_str(WORKER_SCRIPT));
+        let options = BlobPropertyBag::new();
+        options.set_type("text/javascript");
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let script_url = Url::create_object_url_with_blob(&blob)?;
+        let worker = match Worker::new(&script_url) {
+            Ok(worker) => worker,
+            Err(err) => {
+                let _ = Url::revoke_object_url(&script_url);
+                return Err(err);
+            }
+        };
+
+        let port = worker.clone();
+        let tasks = Rc::new(RemoteTasks::new(move |id, delay_ms| {
+            let message = Array::of2(&JsValue::from_f64(id as f64), &JsValue::from_f64(delay_ms));
+            // Plain numbers always survive structured cloning
+            let _ = port.post_message(&message);
+        }));
+        let weak: Weak<RemoteTasks> = Rc::downgrade(&tasks);
+        let listener = EventListener::new(&worker, "message", move |event| {
+            let id = event
+                .dyn_ref::<MessageEvent>()
+                .and_then(|message| message.data().as_f64());
+            if let (Some(tasks), Some(id)) = (weak.upgrade(), id) {
+                tasks.fire(id as u64);
+            }
+        });
+
+        Ok(Self {
+            tasks,
+            worker,
+            script_url,
+            _listener: listener,
+        })
+    }
+}
+
+impl Drop for WorkerScheduler {
+    fn drop(&mut self) {
+        self.worker.terminate();
+        let _ = Url::revoke_object_url(&self.script_url);
+    }
+}
+
+impl Scheduler for WorkerScheduler {
+    fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        self.tasks.schedule(delay_ms, callback)
+    }
+
+    fn schedule_frame(&self, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        BrowserScheduler.schedule_frame(callback)
+    }
+}
+
+/// Native backend using tokio timers
+///
+/// Callbacks are spawned with `tokio::task::spawn_local`, so the timer must
+/// be started from within a `tokio::task::LocalSet`. Pair it with
+/// `clock::TokioClock` so the timer and the scheduler agree on time.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioScheduler;
+
+#[cfg(feature = "tokio")]
+impl Scheduler for TokioScheduler {
+    fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        let task = tokio::task::spawn_local(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms.into())).await;
+            callback();
+        });
+        TaskHandle::new(move || task.abort())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown_timer::{CountdownTimer, TimerConfig, tick_count};
+    use crate::simulation::Simulation;
+
+    #[test]
+    fn test_task_handle_cancels_once() {
+        let cancelled = Rc::new(Cell::new(0));
+        let cancelled_clone = Rc::clone(&cancelled);
+        let handle = TaskHandle::new(move || cancelled_clone.set(cancelled_clone.get() + 1));
+        handle.cancel();
+        assert_eq!(cancelled.get(), 1);
+    }
+
+    #[test]
+    fn test_remote_tasks_bookkeeping() {
+        let posts = Rc::new(RefCell::new(Vec::new()));
+        let posts_clone = Rc::clone(&posts);
+        let tasks = Rc::new(RemoteTasks::new(move |id, delay_ms| {
+            posts_clone.borrow_mut().push((id, delay_ms));
+        }));
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        let task = |name: &'static str| {
+            let ran = Rc::clone(&ran);
+            Box::new(move || ran.borrow_mut().push(name)) as Box<dyn FnOnce()>
+        };
+
+        let first = tasks.schedule(100, task("first"));
+        let second = tasks.schedule(50, task("second"));
+        assert_eq!(*posts.borrow(), vec![(0, 100.0), (1, 50.0)]);
+
+        tasks.fire(1);
+        tasks.fire(1);
+        assert_eq!(*ran.borrow(), vec!["second"]);
+        // Already ran, so there is nothing for the worker to cancel
+        drop(second);
+        assert_eq!(posts.borrow().len(), 2);
+
+        first.cancel();
+        assert_eq!(posts.borrow().last(), Some(&(0, -1.0)));
+        tasks.fire(0);
+        assert_eq!(*ran.borrow(), vec!["second"]);
+        assert!(tasks.tasks.borrow().is_empty());
+    }
+
+    /// Stands in for the worker script: each post becomes a virtual timeout
+    /// that fires the task back, and a negative delay cancels it
+    struct FakeWorker(Rc<RemoteTasks>);
+
+    impl FakeWorker {
+        fn new(sim: &Simulation) -> Self {
+            let scheduler = sim.scheduler();
+            let timeouts = RefCell::new(HashMap::new());
+            let tasks = Rc::new_cyclic(|tasks: &Weak<RemoteTasks>| {
+                let tasks = tasks.clone();
+                RemoteTasks::new(move |id, delay_ms| {
+                    if delay_ms < 0.0 {
+                        timeouts.borrow_mut().remove(&id);
+                        return;
+                    }
+                    let tasks = tasks.clone();
+                    let handle = scheduler.schedule(
+                        delay_ms as u32,
+                        Box::new(move || {
+                            if let Some(tasks) = tasks.upgrade() {
+                                tasks.fire(id);
+                            }
+                        }),
+                    );
+                    timeouts.borrow_mut().insert(id, handle);
+                })
+            });
+            Self(tasks)
+        }
+    }
+
+    impl Scheduler for FakeWorker {
+        fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+            self.0.schedule(delay_ms, callback)
+        }
+    }
+
+    #[test]
+    fn test_worker_protocol_drives_timer() {
+        let sim = Simulation::new();
+        let worker = Rc::new(FakeWorker::new(&sim));
+        let ticks = Rc::new(Cell::new(0));
+        let ticks_clone = Rc::clone(&ticks);
+        let timer = CountdownTimer::with_backend(
+            TimerConfig::default(),
+            sim.clock(),
+            Rc::clone(&worker) as Rc<dyn Scheduler>,
+            tick_count(move |t| ticks_clone.set(t)),
+        );
+
+        timer.start();
+        sim.advance(1050.0);
+        assert_eq!(ticks.get(), 10);
+
+        // Stopping cancels the callback and the worker's timeout
+        timer.stop();
+        assert!(worker.0.tasks.borrow().is_empty());
+        assert_eq!(sim.scheduler().pending(), 0);
+        sim.advance(1000.0);
+        assert_eq!(ticks.get(), 10);
+    }
+
+    #[test]
+    fn test_task_handle_cancels_on_drop() {
+        let cancelled = Rc::new(Cell::new(false));
+        let cancelled_clone = Rc::clone(&cancelled);
+        drop(TaskHandle::new(move || cancelled_clone.set(true)));
+        assert!(cancelled.get());
+    }
+
+    #[cfg(feature = "tokio")]
+    mod tokio_backend {
+        use super::*;
+        use crate::clock::TokioClock;
+        use crate::countdown_timer::{CountdownTimer, TimerConfig, tick_count};
+        use std::time::Duration;
+
+        #[tokio::test(start_paused = true)]
+        async fn test_tokio_backend_drives_timer() {
+            let local = tokio::task::LocalSet::new();
+            local
+                .run_until(async {
+                    let ticks = Rc::new(Cell::new(0));
+                    let ticks_clone = Rc::clone(&ticks);
+                    let timer = CountdownTimer::with_backend(
+                        TimerConfig::default(),
+                        Rc::new(TokioClock::new()),
+                        Rc::new(TokioScheduler),
+                        tick_count(move |t| ticks_clone.set(t)),
+                    );
+
+                    timer.start();
+                    tokio::time::sleep(Duration::from_millis(1050)).await;
+                    assert_eq!(ticks.get(), 10);
+
+                    timer.stop();
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                    assert_eq!(ticks.get(), 10);
+                })
+                .await;
+        }
+    }
+}
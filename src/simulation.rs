@@ -0,0 +1,564 @@
+//! Virtual-time harness for exercising the countdown and round logic
+//!
+//! A [`Simulation`] owns a [`ManualClock`] and a [`VirtualScheduler`] sharing
+//! it, so whole workouts run instantly and deterministically. Tests can make
+//! the next timeout fire late or early and jump the clock without firing
+//! anything, then inspect what the timer did.
+
+use crate::clock::{Clock, ManualClock};
+use crate::countdown_timer::{CountdownTimer, TickEvent, TimerConfig};
+use crate::emomtimer::{BlinkState, TickOutcome, Time, Timer};
+use crate::scheduler::{Scheduler, TaskHandle};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+struct VirtualTask {
+    id: u64,
+    due: f64,
+    callback: Box<dyn FnOnce()>,
+}
+
+/// Scheduler whose callbacks only run when the owning [`Simulation`] advances time
+pub struct VirtualScheduler {
+    clock: Rc<ManualClock>,
+    tasks: Rc<RefCell<Vec<VirtualTask>>>,
+    next_id: Cell<u64>,
+}
+
+impl VirtualScheduler {
+    pub fn new(clock: Rc<ManualClock>) -> Self {
+        Self {
+            clock,
+            tasks: Rc::new(RefCell::new(Vec::new())),
+            next_id: Cell::new(0),
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.tasks.borrow().len()
+    }
+
+    /// Due time of the earliest pending task
+    pub fn next_due(&self) -> Option<f64> {
+        self.tasks.borrow().first().map(|task| task.due)
+    }
+
+    /// Remove and return the earliest task if it is due at or before `until`
+    fn pop_due(&self, until: f64) -> Option<VirtualTask> {
+        let mut tasks = self.tasks.borrow_mut();
+        match tasks.first() {
+            Some(task) if task.due <= until => Some(tasks.remove(0)),
+            _ => None,
+        }
+    }
+
+    /// Shift the earliest pending task by `ms` (negative fires it early)
+    fn shift_next(&self, ms: f64) {
+        let mut tasks = self.tasks.borrow_mut();
+        if let Some(task) = tasks.first_mut() {
+            task.due += ms;
+        }
+        tasks.sort_by(|a, b| a.due.total_cmp(&b.due).then(a.id.cmp(&b.id)));
+    }
+}
+
+impl Scheduler for VirtualScheduler {
+    fn schedule(&self, delay_ms: u32, callback: Box<dyn FnOnce()>) -> TaskHandle {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let due = self.clock.now() + delay_ms as f64;
+
+        let mut tasks = self.tasks.borrow_mut();
+        // Keep tasks ordered by due time, first scheduled first on ties
+        let index = tasks.partition_point(|task| task.due <= due);
+        tasks.insert(index, VirtualTask { id, due, callback });
+        drop(tasks);
+
+        let tasks = Rc::downgrade(&self.tasks);
+        TaskHandle::new(move || {
+            if let Some(tasks) = tasks.upgrade() {
+                tasks.borrow_mut().retain(|task| task.id != id);
+            }
+        })
+    }
+}
+
+/// Virtual clock plus scheduler for driving timers faster than real time
+pub struct Simulation {
+    clock: Rc<ManualClock>,
+    scheduler: Rc<VirtualScheduler>,
+}
+
+impl Simulation {
+    pub fn new() -> Self {
+        let clock = Rc::new(ManualClock::new(0.0));
+        let scheduler = Rc::new(VirtualScheduler::new(Rc::clone(&clock)));
+        Self { clock, scheduler }
+    }
+
+    pub fn clock(&self) -> Rc<ManualClock> {
+        Rc::clone(&self.clock)
+    }
+
+    pub fn scheduler(&self) -> Rc<VirtualScheduler> {
+        Rc::clone(&self.scheduler)
+    }
+
+    pub fn now(&self) -> f64 {
+        self.clock.now()
+    }
+
+    /// Create a countdown timer running on this simulation's virtual time
+    pub fn timer<F>(&self, config: TimerConfig, on_tick: F) -> Rc<CountdownTimer<F>>
+    where
+        F: Fn(TickEvent) + 'static,
+    {
+        CountdownTimer::with_backend(config, self.clock(), self.scheduler(), on_tick)
+    }
+
+    /// Advance virtual time by `ms`, running every callback that falls due
+    /// in order. Each callback sees the clock at its due time, or at the
+    /// current time if the clock has already been jumped past it.
+    pub fn advance(&self, ms: f64) {
+        let until = self.clock.now() + ms;
+        while let Some(task) = self.scheduler.pop_due(until) {
+            if task.due > self.clock.now() {
+                self.clock.set(task.due);
+            }
+            (task.callback)();
+        }
+        self.clock.set(until);
+    }
+
+    /// Run only the next pending callback. Returns false if none is pending.
+    pub fn run_next(&self) -> bool {
+        match self.scheduler.next_due() {
+            Some(due) => {
+                self.advance((due - self.clock.now()).max(0.0));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make the next pending timeout fire `ms` late (or early, if negative)
+    pub fn delay_next(&self, ms: f64) {
+        self.scheduler.shift_next(ms);
+    }
+
+    /// Move the clock by `ms` without running any callbacks, as if the
+    /// host was suspended or the wall clock was changed
+    pub fn jump_clock(&self, ms: f64) {
+        self.clock.advance(ms);
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What the EMOM display showed after one tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub at_ms: f64,
+    pub ticks: usize,
+    pub time: Time,
+    pub round: usize,
+    pub blink: BlinkState,
+    pub outcome: TickOutcome,
+}
+
+type SessionTimer = CountdownTimer<Box<dyn Fn(TickEvent)>>;
+
+/// An EMOM workout wired to a countdown timer the same way the app does,
+/// recording every displayed frame. Use
+/// [`CatchUpPolicy::Coalesce`](crate::countdown_timer::CatchUpPolicy::Coalesce)
+/// in `config` to match the app's handling of a hidden tab.
+pub struct EmomSession {
+    timer: Rc<RefCell<Timer>>,
+    frames: Rc<RefCell<Vec<Frame>>>,
+    countdown: Rc<SessionTimer>,
+}
+
+impl EmomSession {
+    pub fn new(sim: &Simulation, config: TimerConfig, round_time: Time, rounds: usize) -> Self {
+        let timer = Rc::new(RefCell::new(Timer {
+            current_time: round_time,
+            rounds,
+            current_round: 1,
+            running: false,
+        }));
+        let frames = Rc::new(RefCell::new(Vec::new()));
+        let countdown_slot: Rc<RefCell<Weak<SessionTimer>>> = Rc::new(RefCell::new(Weak::new()));
+
+        let timer_clone = Rc::clone(&timer);
+        let frames_clone = Rc::clone(&frames);
+        let countdown_clone = Rc::clone(&countdown_slot);
+        let countdown = sim.timer(
+            config,
+            Box::new(move |event: TickEvent| {
+                // Coalesced ticks are fast-forwarded one by one, as the app
+                // does with `Msg::Tick(event.missed_ticks + 1)`
+                let first_tick = event.tick - event.missed_ticks;
+                let mut timer = timer_clone.borrow_mut();
+                let mut finished = false;
+                for ticks in first_tick..=event.tick {
+                    let outcome = timer.tick(&round_time);
+                    let blink = match outcome {
+                        TickOutcome::Counting | TickOutcome::Idle => timer.blink_state(&round_time),
+                        TickOutcome::RoundEnded | TickOutcome::Finished => BlinkState::None,
+                    };
+                    frames_clone.borrow_mut().push(Frame {
+                        at_ms: event.actual_ms,
+                        ticks,
+                        time: timer.current_time,
+                        round: timer.current_round,
+                        blink,
+                        outcome,
+                    });
+                    if outcome == TickOutcome::Finished {
+                        finished = true;
+                        break;
+                    }
+                }
+                drop(timer);
+
+                if finished && let Some(countdown) = countdown_clone.borrow().upgrade() {
+                    countdown.stop();
+                }
+            }) as Box<dyn Fn(TickEvent)>,
+        );
+        *countdown_slot.borrow_mut() = Rc::downgrade(&countdown);
+
+        let session = Self {
+            timer,
+            frames,
+            countdown,
+        };
+        session.start(&round_time);
+        session
+    }
+
+    fn start(&self, round_time: &Time) {
+        if self.timer.borrow_mut().start(round_time) {
+            self.countdown.start();
+        }
+    }
+
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames.borrow().clone()
+    }
+
+    pub fn current_time(&self) -> Time {
+        self.timer.borrow().current_time
+    }
+
+    pub fn current_round(&self) -> usize {
+        self.timer.borrow().current_round
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.timer.borrow().running
+    }
+
+    pub fn countdown(&self) -> &Rc<SessionTimer> {
+        &self.countdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown_timer::{CatchUpPolicy, tick_count};
+
+    fn seconds(seconds: usize) -> Time {
+        Time {
+            seconds,
+            minutes: 0,
+            tenths: 0,
+        }
+    }
+
+    #[test]
+    fn test_virtual_scheduler_runs_in_due_order() {
+        let sim = Simulation::new();
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let mut handles = Vec::new();
+        for (delay, label) in [(300, "c"), (100, "a"), (200, "b"), (100, "a2")] {
+            let order = Rc::clone(&order);
+            handles.push(
+                sim.scheduler()
+                    .schedule(delay, Box::new(move || order.borrow_mut().push(label))),
+            );
+        }
+
+        sim.advance(250.0);
+        assert_eq!(*order.borrow(), vec!["a", "a2", "b"]);
+        assert_eq!(sim.now(), 250.0);
+        sim.advance(50.0);
+        assert_eq!(*order.borrow(), vec!["a", "a2", "b", "c"]);
+    }
+
+    #[test]
+    fn test_cancelled_task_does_not_run() {
+        let sim = Simulation::new();
+        let ran = Rc::new(Cell::new(false));
+        let ran_clone = Rc::clone(&ran);
+        let handle = sim
+            .scheduler()
+            .schedule(100, Box::new(move || ran_clone.set(true)));
+        handle.cancel();
+        sim.advance(1000.0);
+        assert!(!ran.get());
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_countdown_timer_ticks_on_virtual_time() {
+        let sim = Simulation::new();
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let clock = sim.clock();
+        let timer = sim.timer(
+            TimerConfig::default(),
+            tick_count(move |t| {
+                ticks_clone.borrow_mut().push((t, clock.now()));
+            }),
+        );
+
+        timer.start();
+        sim.advance(500.0);
+        assert_eq!(
+            *ticks.borrow(),
+            vec![(1, 100.0), (2, 200.0), (3, 300.0), (4, 400.0), (5, 500.0)]
+        );
+
+        timer.stop();
+        sim.advance(500.0);
+        assert_eq!(ticks.borrow().len(), 5);
+        assert_eq!(sim.scheduler().pending(), 0);
+    }
+
+    #[test]
+    fn test_late_timeout_is_absorbed_by_next_delay() {
+        let sim = Simulation::new();
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let clock = sim.clock();
+        let timer = sim.timer(
+            TimerConfig::default(),
+            tick_count(move |t| {
+                ticks_clone.borrow_mut().push((t, clock.now()));
+            }),
+        );
+
+        timer.start();
+        sim.advance(100.0);
+        sim.delay_next(60.0);
+        sim.advance(200.0);
+        // Tick 2 fires at 260ms, tick 3 is still scheduled for 300ms
+        assert_eq!(*ticks.borrow(), vec![(1, 100.0), (2, 260.0), (3, 300.0)]);
+    }
+
+    #[test]
+    fn test_early_timeout() {
+        let sim = Simulation::new();
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let clock = sim.clock();
+        let timer = sim.timer(
+            TimerConfig::default(),
+            tick_count(move |t| {
+                ticks_clone.borrow_mut().push((t, clock.now()));
+            }),
+        );
+
+        timer.start();
+        sim.delay_next(-30.0);
+        sim.advance(200.0);
+        assert_eq!(*ticks.borrow(), vec![(1, 70.0), (2, 200.0)]);
+    }
+
+    #[test]
+    fn test_clock_jump_is_corrected_on_sync_tick() {
+        let sim = Simulation::new();
+        let ticks = Rc::new(RefCell::new(Vec::new()));
+        let ticks_clone = Rc::clone(&ticks);
+        let timer = sim.timer(
+            TimerConfig::default(),
+            tick_count(move |t| {
+                ticks_clone.borrow_mut().push(t);
+            }),
+        );
+
+        timer.start();
+        sim.advance(850.0);
+        // Host is suspended for two seconds
+        sim.jump_clock(2000.0);
+        sim.advance(0.0);
+        sim.advance(300.0);
+        assert_eq!(
+            *ticks.borrow(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 28, 29, 30, 31]
+        );
+    }
+
+    #[test]
+    fn test_run_next() {
+        let sim = Simulation::new();
+        let count = Rc::new(Cell::new(0));
+        let count_clone = Rc::clone(&count);
+        let timer = sim.timer(
+            TimerConfig::default(),
+            tick_count(move |t| count_clone.set(t)),
+        );
+
+        assert!(!sim.run_next());
+        timer.start();
+        assert!(sim.run_next());
+        assert!(sim.run_next());
+        assert_eq!(count.get(), 2);
+        assert_eq!(sim.now(), 200.0);
+    }
+
+    #[test]
+    fn test_emom_session_full_sequence() {
+        let sim = Simulation::new();
+        let session = EmomSession::new(&sim, TimerConfig::default(), seconds(10), 2);
+
+        // Starting shows one tick into the round
+        assert_eq!(
+            session.current_time(),
+            Time {
+                seconds: 9,
+                minutes: 0,
+                tenths: 9
+            }
+        );
+
+        sim.advance(30_000.0);
+        let frames = session.frames();
+
+        // Time::tick goes from 1.0 straight to 0.0, so each round is 90 ticks
+        assert_eq!(frames.len(), 180);
+        assert!(!session.is_running());
+        assert!(!session.countdown().is_running());
+
+        // Round 1 counts from 9.8 after the first tick
+        assert_eq!(frames[0].time.seconds, 9);
+        assert_eq!(frames[0].time.tenths, 8);
+        assert_eq!(frames[0].round, 1);
+        assert_eq!(frames[0].at_ms, 100.0);
+
+        // Round 1 ends and round 2 starts on the same tick
+        let round_end = frames
+            .iter()
+            .position(|f| f.outcome == TickOutcome::RoundEnded)
+            .unwrap();
+        assert_eq!(round_end, 89);
+        assert_eq!(frames[round_end].round, 2);
+        assert_eq!(frames[round_end].time.seconds, 9);
+        assert_eq!(frames[round_end].time.tenths, 9);
+
+        // Last frame finishes and restores the round time
+        let last = frames.last().unwrap();
+        assert_eq!(last.outcome, TickOutcome::Finished);
+        assert_eq!(last.time, seconds(10));
+        assert_eq!(last.round, 2);
+        assert_eq!(last.at_ms, 18_000.0);
+
+        // Every frame is exactly one tenth after the previous
+        for pair in frames.windows(2) {
+            assert_eq!(pair[1].at_ms - pair[0].at_ms, 100.0);
+            assert_eq!(pair[1].ticks, pair[0].ticks + 1);
+        }
+    }
+
+    #[test]
+    fn test_emom_session_hidden_tab_fast_forwards() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            ..TimerConfig::default()
+        };
+        let session = EmomSession::new(&sim, config, seconds(10), 2);
+
+        // The tab is hidden and its timeout throttled, then shown again
+        sim.advance(350.0);
+        sim.delay_next(5000.0);
+        sim.advance(2000.0);
+        session.countdown().resync();
+
+        let frames = session.frames();
+        assert_eq!(session.countdown().elapsed_ticks(), 23);
+        assert_eq!(frames.len(), 23);
+        assert_eq!(
+            frames.iter().map(|f| f.ticks).collect::<Vec<_>>(),
+            (1..=23).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            session.current_time(),
+            Time {
+                seconds: 7,
+                minutes: 0,
+                tenths: 6
+            }
+        );
+        assert_eq!(session.current_round(), 1);
+    }
+
+    #[test]
+    fn test_emom_session_one_second_round() {
+        let sim = Simulation::new();
+        let session = EmomSession::new(&sim, TimerConfig::default(), seconds(1), 1);
+
+        // Starting shows 0.9, one tick into the round, and counts every tenth
+        // from there instead of finishing on the first tick
+        assert_eq!(
+            session.current_time(),
+            Time {
+                seconds: 0,
+                minutes: 0,
+                tenths: 9
+            }
+        );
+        sim.advance(5000.0);
+        let frames = session.frames();
+        assert_eq!(frames.len(), 9);
+        assert_eq!(frames.last().unwrap().outcome, TickOutcome::Finished);
+        assert_eq!(frames.last().unwrap().at_ms, 900.0);
+    }
+
+    #[test]
+    fn test_emom_session_blink_states() {
+        let sim = Simulation::new();
+        let session = EmomSession::new(&sim, TimerConfig::default(), seconds(10), 2);
+        sim.advance(30_000.0);
+
+        let blink_at = |round: usize, seconds: usize, tenths: usize| {
+            session
+                .frames()
+                .iter()
+                .find(|f| f.round == round && f.time.seconds == seconds && f.time.tenths == tenths)
+                .unwrap()
+                .blink
+        };
+
+        // No green blink in round 1, green in round 2
+        assert_eq!(blink_at(1, 9, 4), BlinkState::None);
+        assert_eq!(blink_at(2, 9, 4), BlinkState::Green);
+        assert_eq!(blink_at(2, 9, 5), BlinkState::None);
+        assert_eq!(blink_at(2, 7, 0), BlinkState::Green);
+        assert_eq!(blink_at(2, 6, 4), BlinkState::None);
+
+        // Red at the last three seconds of every round
+        for round in 1..=2 {
+            assert_eq!(blink_at(round, 3, 4), BlinkState::Red);
+            assert_eq!(blink_at(round, 3, 5), BlinkState::None);
+            assert_eq!(blink_at(round, 1, 0), BlinkState::Red);
+            assert_eq!(blink_at(round, 4, 0), BlinkState::None);
+        }
+    }
+}
@@ -0,0 +1,540 @@
+//! Many logical timers driven by one scheduled callback chain
+//!
+//! A round clock, a total workout clock and a rest clock built as separate
+//! [`CountdownTimer`](crate::countdown_timer::CountdownTimer)s each run their
+//! own timeout chain and drift math, so they can disagree by a tick. A
+//! [`TimerGroup`] reads the clock once per tick, corrects drift once, and
+//! hands the same instant to every member.
+
+use crate::clock::{Clock, PerformanceClock};
+use crate::countdown_timer::{
+    CatchUpPolicy, TickEvent, TimerConfig, TimerConfigError, TimerState, TimerStats,
+};
+use crate::scheduler::{BrowserScheduler, Scheduler, TaskHandle};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+struct GroupInner {
+    config: TimerConfig,
+    clock: Rc<dyn Clock>,
+    scheduler: Rc<dyn Scheduler>,
+    state: RefCell<TimerState>,
+    timeout_handle: Cell<Option<TaskHandle>>,
+    members: RefCell<Vec<Weak<Member>>>,
+}
+
+impl GroupInner {
+    /// Start the shared timeline if no member is keeping it going
+    fn ensure_running(self: &Rc<Self>) {
+        let mut state = self.state.borrow_mut();
+        if state.running {
+            return;
+        }
+        state.begin(&self.config, self.clock.now());
+        state.align(&self.config, self.clock.now(), self.clock.epoch_ms());
+        drop(state);
+
+        self.schedule_tick();
+    }
+
+    fn live_members(&self) -> Vec<Rc<Member>> {
+        let mut members = self.members.borrow_mut();
+        members.retain(|member| member.strong_count() > 0);
+        members.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    fn schedule_tick(self: &Rc<Self>) {
+        let delay = self.state.borrow().delay_until_next(self.clock.now());
+
+        // Weak so a pending tick does not keep a dropped group alive
+        let group = Rc::downgrade(self);
+        let handle = self.scheduler.schedule(
+            delay,
+            Box::new(move || {
+                if let Some(group) = group.upgrade() {
+                    group.tick();
+                }
+            }),
+        );
+        self.timeout_handle.set(Some(handle));
+    }
+
+    fn tick(self: &Rc<Self>) {
+        self.timeout_handle.take();
+
+        let mut state = self.state.borrow_mut();
+        if !state.running {
+            return;
+        }
+        let event = state.advance(&self.config, self.clock.now());
+        let missed = state.missed_ticks;
+        drop(state);
+
+        for member in self.live_members() {
+            member.tick(&self.config, event, missed);
+        }
+
+        let mut state = self.state.borrow_mut();
+        if !state.running {
+            return;
+        }
+        if self.live_members().iter().any(|member| member.is_running()) {
+            drop(state);
+            self.schedule_tick();
+        } else {
+            // Nobody is listening; the next member start begins a new timeline
+            state.running = false;
+        }
+    }
+}
+
+struct MemberState {
+    running: bool,
+    paused: bool,
+    ticks_elapsed: usize,
+    /// Group tick and member tick count when the member last started or resumed
+    joined_at: usize,
+    ticks_at_join: usize,
+    target_ticks: Option<usize>,
+}
+
+struct Member {
+    on_tick: Box<dyn Fn(TickEvent)>,
+    on_complete: RefCell<Option<Rc<dyn Fn()>>>,
+    state: RefCell<MemberState>,
+}
+
+impl Member {
+    fn is_running(&self) -> bool {
+        self.state.borrow().running
+    }
+
+    /// Map one group tick onto this member's count and deliver it
+    fn tick(&self, config: &TimerConfig, event: TickEvent, missed: usize) {
+        let mut state = self.state.borrow_mut();
+        if !state.running {
+            return;
+        }
+        let previous = state.ticks_elapsed;
+        let mut ticks = state.ticks_at_join + event.tick.saturating_sub(state.joined_at);
+        if let Some(target) = state.target_ticks {
+            ticks = ticks.min(target);
+        }
+        state.ticks_elapsed = ticks;
+        let complete = state.target_ticks.is_some_and(|target| ticks >= target);
+        if complete {
+            state.running = false;
+        }
+        drop(state);
+
+        match config.catch_up {
+            CatchUpPolicy::Burst => {
+                // Replay missed ticks, but never ones from before this member joined
+                let first = (previous + 1).max(ticks.saturating_sub(missed)).min(ticks);
+                for tick in first..=ticks {
+                    (self.on_tick)(TickEvent { tick, ..event });
+                }
+            }
+            CatchUpPolicy::Skip => (self.on_tick)(TickEvent {
+                tick: ticks,
+                ..event
+            }),
+            // Only report ticks this member would have counted itself
+            CatchUpPolicy::Coalesce => (self.on_tick)(TickEvent {
+                tick: ticks,
+                missed_ticks: ticks.saturating_sub(previous + 1),
+                ..event
+            }),
+        }
+        if complete {
+            let on_complete = self.on_complete.borrow().clone();
+            if let Some(on_complete) = on_complete {
+                on_complete();
+            }
+        }
+    }
+}
+
+/// One scheduling loop shared by any number of [`GroupTimer`]s
+///
+/// The loop runs while at least one member is running, and members keep it
+/// alive after the group itself is dropped. Members that start while it runs
+/// join its tick phase, so their first tick may come early. The config means
+/// the same as for a [`CountdownTimer`](crate::countdown_timer::CountdownTimer):
+/// `speed` and `align_to_wall_clock` shape the shared timeline and
+/// `catch_up` applies to each member.
+pub struct TimerGroup {
+    inner: Rc<GroupInner>,
+}
+
+impl TimerGroup {
+    /// Create a group driven by `performance.now()` and `setTimeout`
+    pub fn new(config: TimerConfig) -> Self {
+        Self::with_backend(config, Rc::new(PerformanceClock), Rc::new(BrowserScheduler))
+    }
+
+    /// Like `new`, but rejects an invalid `config`
+    pub fn try_new(config: TimerConfig) -> Result<Self, TimerConfigError> {
+        config.validate()?;
+        Ok(Self::new(config))
+    }
+
+    /// Create a group reading time from `clock` and deferring ticks through `scheduler`
+    pub fn with_backend(
+        config: TimerConfig,
+        clock: Rc<dyn Clock>,
+        scheduler: Rc<dyn Scheduler>,
+    ) -> Self {
+        Self {
+            inner: Rc::new(GroupInner {
+                config,
+                clock,
+                scheduler,
+                state: RefCell::new(TimerState::idle()),
+                timeout_handle: Cell::new(None),
+                members: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Add a stopped member timer; it leaves the group when dropped
+    pub fn add(&self, on_tick: impl Fn(TickEvent) + 'static) -> GroupTimer {
+        let member = Rc::new(Member {
+            on_tick: Box::new(on_tick),
+            on_complete: RefCell::new(None),
+            state: RefCell::new(MemberState {
+                running: false,
+                paused: false,
+                ticks_elapsed: 0,
+                joined_at: 0,
+                ticks_at_join: 0,
+                target_ticks: None,
+            }),
+        });
+        self.inner.members.borrow_mut().push(Rc::downgrade(&member));
+        GroupTimer {
+            group: Rc::clone(&self.inner),
+            member,
+        }
+    }
+
+    /// Whether the shared loop is scheduling ticks
+    pub fn is_running(&self) -> bool {
+        self.inner.state.borrow().running
+    }
+
+    /// Drift and jitter diagnostics for the shared loop since it last started
+    pub fn stats(&self) -> TimerStats {
+        self.inner.state.borrow().stats.clone()
+    }
+}
+
+/// A logical timer in a [`TimerGroup`], with its own tick count and target
+pub struct GroupTimer {
+    group: Rc<GroupInner>,
+    member: Rc<Member>,
+}
+
+impl GroupTimer {
+    fn join(&self, ticks: usize) {
+        self.group.ensure_running();
+        let group_ticks = self.group.state.borrow().ticks_elapsed;
+        let mut state = self.member.state.borrow_mut();
+        state.running = true;
+        state.paused = false;
+        state.ticks_elapsed = ticks;
+        state.joined_at = group_ticks;
+        state.ticks_at_join = ticks;
+    }
+
+    /// Start counting from zero on the group's next tick
+    pub fn start(&self) {
+        if self.is_running() {
+            return;
+        }
+        self.join(0);
+    }
+
+    pub fn pause(&self) {
+        let mut state = self.member.state.borrow_mut();
+        if state.running {
+            state.running = false;
+            state.paused = true;
+        }
+    }
+
+    /// Continue a paused member from its tick count
+    pub fn resume(&self) {
+        let state = self.member.state.borrow();
+        if state.running || !state.paused {
+            return;
+        }
+        let ticks = state.ticks_elapsed;
+        drop(state);
+        self.join(ticks);
+    }
+
+    pub fn stop(&self) {
+        let mut state = self.member.state.borrow_mut();
+        state.running = false;
+        state.paused = false;
+    }
+
+    pub fn reset(&self) {
+        self.stop();
+        self.member.state.borrow_mut().ticks_elapsed = 0;
+    }
+
+    /// Stop after `target_ticks` and call `on_complete` once
+    pub fn set_target(&self, target_ticks: usize, on_complete: impl Fn() + 'static) {
+        self.member.state.borrow_mut().target_ticks = Some(target_ticks);
+        *self.member.on_complete.borrow_mut() = Some(Rc::new(on_complete));
+    }
+
+    /// Ticks left until the target, None if no target is set
+    pub fn remaining(&self) -> Option<usize> {
+        let state = self.member.state.borrow();
+        state
+            .target_ticks
+            .map(|target| target.saturating_sub(state.ticks_elapsed))
+    }
+
+    pub fn elapsed_ticks(&self) -> usize {
+        self.member.state.borrow().ticks_elapsed
+    }
+
+    /// Time counted so far by this member, before `speed` scaling
+    pub fn elapsed(&self) -> Duration {
+        self.group.config.ticks_to_duration(self.elapsed_ticks())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.member.is_running()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.member.state.borrow().paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+
+    type Log = Rc<RefCell<Vec<(&'static str, usize, f64)>>>;
+
+    fn logging(log: &Log, name: &'static str) -> impl Fn(TickEvent) + 'static {
+        let log = Rc::clone(log);
+        move |event| log.borrow_mut().push((name, event.tick, event.actual_ms))
+    }
+
+    fn group(sim: &Simulation, config: TimerConfig) -> TimerGroup {
+        TimerGroup::with_backend(config, sim.clock(), sim.scheduler())
+    }
+
+    #[test]
+    fn test_members_share_one_loop() {
+        let sim = Simulation::new();
+        let group = group(&sim, TimerConfig::default());
+        let log: Log = Rc::new(RefCell::new(Vec::new()));
+        let workout = group.add(logging(&log, "workout"));
+        let round = group.add(logging(&log, "round"));
+
+        workout.start();
+        round.start();
+        assert_eq!(sim.scheduler().pending(), 1);
+        sim.advance(200.0);
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                ("workout", 1, 100.0),
+                ("round", 1, 100.0),
+                ("workout", 2, 200.0),
+                ("round", 2, 200.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_late_member_joins_tick_phase() {
+        let sim = Simulation::new();
+        let group = group(&sim, TimerConfig::default());
+        let log: Log = Rc::new(RefCell::new(Vec::new()));
+        let workout = group.add(logging(&log, "workout"));
+        let rest = group.add(logging(&log, "rest"));
+
+        workout.start();
+        sim.advance(250.0);
+        rest.start();
+        sim.advance(100.0);
+        assert_eq!(
+            log.borrow()[2..],
+            [("workout", 3, 300.0), ("rest", 1, 300.0)]
+        );
+        assert_eq!(workout.elapsed_ticks(), 3);
+        assert_eq!(rest.elapsed_ticks(), 1);
+        assert_eq!(rest.elapsed(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_member_target_pause_and_resume() {
+        let sim = Simulation::new();
+        let group = group(&sim, TimerConfig::default());
+        let log: Log = Rc::new(RefCell::new(Vec::new()));
+        let workout = group.add(logging(&log, "workout"));
+        let round = group.add(logging(&log, "round"));
+        let rounds = Rc::new(Cell::new(0));
+        let rounds_clone = Rc::clone(&rounds);
+        round.set_target(10, move || rounds_clone.set(rounds_clone.get() + 1));
+
+        workout.start();
+        round.start();
+        sim.advance(1500.0);
+        assert_eq!(rounds.get(), 1);
+        assert!(!round.is_running());
+        assert_eq!(round.remaining(), Some(0));
+        assert_eq!(workout.elapsed_ticks(), 15);
+
+        workout.pause();
+        assert!(workout.is_paused());
+        sim.advance(1000.0);
+        // No member running, so the loop stopped
+        assert!(!group.is_running());
+        assert_eq!(sim.scheduler().pending(), 0);
+
+        workout.resume();
+        sim.advance(100.0);
+        assert_eq!(log.borrow().last(), Some(&("workout", 16, 2600.0)));
+    }
+
+    #[test]
+    fn test_correction_applies_to_every_member() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Burst,
+            ..TimerConfig::default()
+        };
+        let group = group(&sim, config);
+        let log: Log = Rc::new(RefCell::new(Vec::new()));
+        let a = group.add(logging(&log, "a"));
+        let b = group.add(logging(&log, "b"));
+        a.start();
+        b.start();
+
+        sim.advance(950.0);
+        sim.delay_next(250.0);
+        sim.advance(300.0);
+        assert_eq!(group.stats().corrections, 1);
+        assert_eq!(a.elapsed_ticks(), 12);
+        assert_eq!(b.elapsed_ticks(), 12);
+        // Each member replays the missed ticks
+        let a_ticks: Vec<_> = log
+            .borrow()
+            .iter()
+            .filter(|(name, _, _)| *name == "a")
+            .map(|(_, tick, _)| *tick)
+            .collect();
+        assert_eq!(a_ticks, (1..=12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_config() {
+        let config = TimerConfig {
+            interval_ms: 0,
+            ..TimerConfig::default()
+        };
+        assert!(matches!(
+            TimerGroup::try_new(config),
+            Err(TimerConfigError::ZeroInterval)
+        ));
+        assert!(TimerGroup::try_new(TimerConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_member_matches_standalone_timer() {
+        type Events = Rc<RefCell<Vec<(usize, f64, usize)>>>;
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            align_to_wall_clock: true,
+            speed: 2.0,
+            ..TimerConfig::default()
+        };
+        let record = |events: &Events| {
+            let events = Rc::clone(events);
+            move |event: TickEvent| {
+                events
+                    .borrow_mut()
+                    .push((event.tick, event.actual_ms, event.missed_ticks))
+            }
+        };
+        // Start off a wall-clock boundary, then fall behind
+        let run = |sim: &Simulation, start: &dyn Fn()| {
+            sim.advance(1234.0);
+            start();
+            sim.advance(500.0);
+            sim.delay_next(300.0);
+            sim.advance(1000.0);
+        };
+
+        let sim = Simulation::new();
+        let standalone: Events = Rc::new(RefCell::new(Vec::new()));
+        let timer = sim.timer(config, record(&standalone));
+        run(&sim, &|| timer.start());
+
+        let sim = Simulation::new();
+        let grouped: Events = Rc::new(RefCell::new(Vec::new()));
+        let group = group(&sim, config);
+        let member = group.add(record(&grouped));
+        run(&sim, &|| member.start());
+
+        assert_eq!(standalone.borrow()[0], (1, 1300.0, 0));
+        assert!(standalone.borrow().iter().any(|&(_, _, missed)| missed > 0));
+        assert_eq!(*grouped.borrow(), *standalone.borrow());
+    }
+
+    #[test]
+    fn test_late_member_coalesces_only_its_own_ticks() {
+        let sim = Simulation::new();
+        let config = TimerConfig {
+            catch_up: CatchUpPolicy::Coalesce,
+            ..TimerConfig::default()
+        };
+        let group = group(&sim, config);
+        let missed = Rc::new(RefCell::new(Vec::new()));
+        let missed_clone = Rc::clone(&missed);
+        let workout = group.add(|_| {});
+        let rest = group.add(move |event| {
+            missed_clone
+                .borrow_mut()
+                .push((event.tick, event.missed_ticks))
+        });
+
+        workout.start();
+        sim.advance(850.0);
+        rest.start();
+        // Rest's first tick is late, then the sync tick jumps the group from
+        // 9 to 12; rest only missed the two ticks since its own first one
+        sim.delay_next(350.0);
+        sim.advance(400.0);
+        assert_eq!(workout.elapsed_ticks(), 12);
+        assert_eq!(*missed.borrow(), vec![(1, 0), (4, 2)]);
+    }
+
+    #[test]
+    fn test_dropped_member_leaves_group() {
+        let sim = Simulation::new();
+        let group = group(&sim, TimerConfig::default());
+        let log: Log = Rc::new(RefCell::new(Vec::new()));
+        let a = group.add(logging(&log, "a"));
+        a.start();
+        sim.advance(100.0);
+
+        drop(a);
+        sim.advance(100.0);
+        assert_eq!(log.borrow().len(), 1);
+        assert!(!group.is_running());
+        assert!(group.inner.members.borrow().is_empty());
+    }
+}